@@ -14,7 +14,7 @@ impl BitCode for u8 {
     }
 
     fn to_64(&self) -> u64 {
-        self.clone() as u64
+        *self as u64
     }
 
     fn bit_width(&self) -> usize {
@@ -28,7 +28,7 @@ impl BitCode for u16 {
     }
 
     fn to_64(&self) -> u64 {
-        self.clone() as u64
+        *self as u64
     }
 
     fn bit_width(&self) -> usize {
@@ -42,7 +42,7 @@ impl BitCode for u32 {
     }
 
     fn to_64(&self) -> u64 {
-        self.clone() as u64
+        *self as u64
     }
 
     fn bit_width(&self) -> usize {
@@ -52,11 +52,11 @@ impl BitCode for u32 {
 
 impl BitCode for u64 {
     fn new(val: u64) -> Self {
-        val as u64
+        val
     }
 
     fn to_64(&self) -> u64 {
-        self.clone()
+        *self
     }
 
     fn bit_width(&self) -> usize {
@@ -65,12 +65,12 @@ impl BitCode for u64 {
 }
 
 pub fn bitmatch_upper<T: BitCode>(bitcode: &T, pattern: &str) -> bool {
-    if pattern.len() == 0 {
+    if pattern.is_empty() {
         return false;
     }
     let normalized_pattern: String = pattern.replace(" ", "");
     let width: usize = bitcode.bit_width();
-    let bitcode64: u64 = bitcode.to_64() as u64;
+    let bitcode64: u64 = bitcode.to_64();
     let mut code: u64 = 0;
     let mut mask: u64 = 0;
     let mut pattern_length: usize = 0;
@@ -79,26 +79,23 @@ pub fn bitmatch_upper<T: BitCode>(bitcode: &T, pattern: &str) -> bool {
     // println!("pt:{}", normalized_pattern);
     for c in normalized_pattern.chars() {
         if enable_shift {
-            code = code << 1;
-            mask = mask << 1;
+            code <<= 1;
+            mask <<= 1;
         }
         match c {
             '0' => {
-                code = code | 0b0;
-                mask = mask | 0b1;
+                mask |= 0b1;
                 enable_shift = true;
                 pattern_length += 1;
             }
             '1' => {
-                code = code | 0b1;
-                mask = mask | 0b1;
+                code |= 0b1;
+                mask |= 0b1;
                 enable_shift = true;
                 pattern_length += 1;
             }
             ' ' => enable_shift = false,
             _ => {
-                code = code | 0b0;
-                mask = mask | 0b0;
                 enable_shift = true;
                 pattern_length += 1;
             }
@@ -107,41 +104,38 @@ pub fn bitmatch_upper<T: BitCode>(bitcode: &T, pattern: &str) -> bool {
         // println!("code:{:0b} mask:{:0b}", code, mask);
     }
     // println!("code:{:0b} mask:{:0b} bitcode:{:0b}", code, mask, bitcode64);
-    code = code << (width - pattern_length);
-    mask = mask << (width - pattern_length);
+    code <<= width - pattern_length;
+    mask <<= width - pattern_length;
     // println!("code:{:032b} mask:{:032b} bitcode:{:032b}", code, mask, bitcode64);
     (bitcode64 & mask) == code
 }
 
 pub fn bitmatch_lower<T: BitCode>(bitcode: &T, pattern: &str) -> bool {
-    if pattern.len() == 0 {
+    if pattern.is_empty() {
         return false;
     }
     let normalized_pattern: String = pattern.replace(" ", "");
-    let bitcode64: u64 = bitcode.to_64() as u64;
+    let bitcode64: u64 = bitcode.to_64();
     let mut code: u64 = 0;
     let mut mask: u64 = 0;
     let mut enable_shift: bool = false;
     for c in normalized_pattern.chars() {
         if enable_shift {
-            code = code << 1;
-            mask = mask << 1;
+            code <<= 1;
+            mask <<= 1;
         }
         match c {
             '0' => {
-                code = code | 0b0;
-                mask = mask | 0b1;
+                mask |= 0b1;
                 enable_shift = true;
             }
             '1' => {
-                code = code | 0b1;
-                mask = mask | 0b1;
+                code |= 0b1;
+                mask |= 0b1;
                 enable_shift = true;
             }
             ' ' => enable_shift = false,
             _ => {
-                code = code | 0b0;
-                mask = mask | 0b0;
                 enable_shift = true;
             }
         }
@@ -159,13 +153,13 @@ pub fn check_bitcode_upper<T: BitCode>(bitcode: &T, cond: &str, exclude: &str) -
     // println!("cond:{}", cond);
     for ex in exclude_list {
         // println!("ex:{}", ex);
-        if bitmatch_upper(bitcode, ex) == true {
+        if bitmatch_upper(bitcode, ex) {
             return false;
         }
     }
     for cd in cond_list {
         // println!("cd:{}", cd);
-        if bitmatch_upper(bitcode, cd) == true {
+        if bitmatch_upper(bitcode, cd) {
             return true;
         }
     }
@@ -179,13 +173,13 @@ pub fn check_bitcode_lower<T: BitCode>(bitcode: &T, cond: &str, exclude: &str) -
     // println!("cond:{}", cond);
     for ex in exclude_list {
         // println!("ex:{}", ex);
-        if bitmatch_lower(bitcode, ex) == true {
+        if bitmatch_lower(bitcode, ex) {
             return false;
         }
     }
     for cd in cond_list {
         // println!("cd:{}", cd);
-        if bitmatch_lower(bitcode, cd) == true {
+        if bitmatch_lower(bitcode, cd) {
             return true;
         }
     }
@@ -242,9 +236,7 @@ pub fn parse_bit_u<T: BitCode>(bitcode: &T, format: &str) -> Result<HashMap<Stri
                 }
             }
         }
-        if current_bit > 0 {
-            current_bit -= 1;
-        }
+        current_bit = current_bit.saturating_sub(1);
     }
 
     Ok(result)
@@ -293,169 +285,145 @@ mod tests {
     #[test]
     fn test_bitmach_16_1() {
         let bitcode: u16 = 0b1;
-        assert_eq!(bitmatch_upper(&bitcode, "_______________1"), true);
-        assert_eq!(bitmatch_upper(&bitcode, "____ ____ ____ ___1"), true);
-        assert_eq!(
-            bitmatch_upper(&bitcode, "_ _ _ _ _ _ _ _ _ _ _ _ _ _ _1"),
-            true
-        );
-        assert_eq!(
-            bitmatch_upper(
-                &bitcode,
-                "  _   _   _   _   _   _   _   _   _   _   _   _   _   _ _  1"
-            ),
-            true
-        );
-        assert_eq!(
-            bitmatch_upper(
-                &bitcode,
-                "  _   _   _   _   _   _   _   _   _   _   _   _   _   _ _  1    "
-            ),
-            true
-        );
-        assert_eq!(bitmatch_upper(&bitcode, "_______________0"), false);
-        assert_eq!(bitmatch_upper(&bitcode, "________________"), true);
+        assert!(bitmatch_upper(&bitcode, "_______________1"));
+        assert!(bitmatch_upper(&bitcode, "____ ____ ____ ___1"));
+        assert!(bitmatch_upper(&bitcode, "_ _ _ _ _ _ _ _ _ _ _ _ _ _ _1"));
+        assert!(bitmatch_upper(
+            &bitcode,
+            "  _   _   _   _   _   _   _   _   _   _   _   _   _   _ _  1"
+        ));
+        assert!(bitmatch_upper(
+            &bitcode,
+            "  _   _   _   _   _   _   _   _   _   _   _   _   _   _ _  1    "
+        ));
+        assert!(!bitmatch_upper(&bitcode, "_______________0"));
+        assert!(bitmatch_upper(&bitcode, "________________"));
 
         for i in 1..16 {
-            assert_eq!(bitmatch_upper(&(bitcode << i), "_______________1"), false);
-            assert_eq!(bitmatch_upper(&(bitcode << i), "_______________0"), true);
-            assert_eq!(bitmatch_upper(&(bitcode << i), "________________"), true);
+            assert!(!bitmatch_upper(&(bitcode << i), "_______________1"));
+            assert!(bitmatch_upper(&(bitcode << i), "_______________0"));
+            assert!(bitmatch_upper(&(bitcode << i), "________________"));
         }
     }
 
     #[test]
     fn test_bitmach_16_2() {
         let bitcode: u16 = 0b1111111111111111;
-        assert_eq!(bitmatch_upper(&bitcode, "1111111111111111"), true);
-        assert_eq!(bitmatch_upper(&bitcode, "0000000000000000"), false);
+        assert!(bitmatch_upper(&bitcode, "1111111111111111"));
+        assert!(!bitmatch_upper(&bitcode, "0000000000000000"));
 
-        assert_eq!(bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"), true);
-        assert_eq!(bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"), false);
+        assert!(bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"));
+        assert!(!bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"));
 
         let bitcode: u16 = 0b0101010101010101;
-        assert_eq!(bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"), false);
-        assert_eq!(bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"), true);
+        assert!(!bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"));
+        assert!(bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"));
 
         let bitcode_s: u16 = bitcode << 1;
-        assert_eq!(bitmatch_upper(&bitcode_s, "1_1_1_1_1_1_1_1_"), true);
-        assert_eq!(bitmatch_upper(&bitcode_s, "0_0_0_0_0_0_0_0_"), false);
+        assert!(bitmatch_upper(&bitcode_s, "1_1_1_1_1_1_1_1_"));
+        assert!(!bitmatch_upper(&bitcode_s, "0_0_0_0_0_0_0_0_"));
 
         let bitcode_s: u16 = bitcode << 8;
-        assert_eq!(bitmatch_upper(&bitcode_s, "1_1_1_1_________"), false);
-        assert_eq!(bitmatch_upper(&bitcode_s, "0_0_0_0_________"), true);
+        assert!(!bitmatch_upper(&bitcode_s, "1_1_1_1_________"));
+        assert!(bitmatch_upper(&bitcode_s, "0_0_0_0_________"));
 
         let bitcode_s: u16 = bitcode << 9;
-        assert_eq!(bitmatch_upper(&bitcode_s, "1_1_1_1_________"), true);
-        assert_eq!(bitmatch_upper(&bitcode_s, "0_0_0_0_________"), false);
+        assert!(bitmatch_upper(&bitcode_s, "1_1_1_1_________"));
+        assert!(!bitmatch_upper(&bitcode_s, "0_0_0_0_________"));
     }
 
     #[test]
     fn test_bitmach_16_3() {
         let bitcode: u16 = 0b1111111100000000;
-        assert_eq!(bitmatch_upper(&bitcode, "11111111"), true);
-        assert_eq!(bitmatch_lower(&bitcode, "11111111"), false);
-        assert_eq!(bitmatch_upper(&bitcode, "00000000"), false);
-        assert_eq!(bitmatch_lower(&bitcode, "00000000"), true);
+        assert!(bitmatch_upper(&bitcode, "11111111"));
+        assert!(!bitmatch_lower(&bitcode, "11111111"));
+        assert!(!bitmatch_upper(&bitcode, "00000000"));
+        assert!(bitmatch_lower(&bitcode, "00000000"));
     }
 
     #[test]
     fn test_bitmach_32_1() {
         let bitcode: u32 = 0b1;
-        assert_eq!(
-            bitmatch_upper(&bitcode, "_______________________________1"),
-            true
-        );
-        assert_eq!(
-            bitmatch_upper(&bitcode, "_______________________________0"),
-            false
-        );
-        assert_eq!(
-            bitmatch_upper(&bitcode, "________________________________"),
-            true
-        );
+        assert!(bitmatch_upper(&bitcode, "_______________________________1"));
+        assert!(!bitmatch_upper(
+            &bitcode,
+            "_______________________________0"
+        ));
+        assert!(bitmatch_upper(&bitcode, "________________________________"));
 
         for i in 1..32 {
-            assert_eq!(
-                bitmatch_upper(&(bitcode << i), "_______________________________1"),
-                false
-            );
-            assert_eq!(
-                bitmatch_upper(&(bitcode << i), "_______________________________0"),
-                true
-            );
-            assert_eq!(
-                bitmatch_upper(&(bitcode << i), "________________________________"),
-                true
-            );
+            assert!(!bitmatch_upper(
+                &(bitcode << i),
+                "_______________________________1"
+            ));
+            assert!(bitmatch_upper(
+                &(bitcode << i),
+                "_______________________________0"
+            ));
+            assert!(bitmatch_upper(
+                &(bitcode << i),
+                "________________________________"
+            ));
         }
     }
 
     #[test]
     fn test_bitmach_32_2() {
         let bitcode: u32 = 0b11111111111111110000000000000000;
-        assert_eq!(bitmatch_upper(&bitcode, "1111111111111111"), true);
-        assert_eq!(bitmatch_upper(&bitcode, "0000000000000000"), false);
+        assert!(bitmatch_upper(&bitcode, "1111111111111111"));
+        assert!(!bitmatch_upper(&bitcode, "0000000000000000"));
 
-        assert_eq!(bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"), true);
-        assert_eq!(bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"), false);
+        assert!(bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"));
+        assert!(!bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"));
 
         let bitcode: u32 = 0b01010101010101010000000000000000;
-        assert_eq!(bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"), false);
-        assert_eq!(bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"), true);
-        assert_eq!(bitmatch_upper(&(bitcode << 1), "1_1_1_1_1_1_1_1_"), true);
-        assert_eq!(bitmatch_upper(&(bitcode << 1), "0_0_0_0_0_0_0_0_"), false);
-        assert_eq!(bitmatch_upper(&(bitcode << 8), "1_1_1_1_________"), false);
-        assert_eq!(bitmatch_upper(&(bitcode << 8), "0_0_0_0_________"), true);
-        assert_eq!(bitmatch_upper(&(bitcode << 9), "1_1_1_1_________"), true);
-        assert_eq!(bitmatch_upper(&(bitcode << 9), "0_0_0_0_________"), false);
+        assert!(!bitmatch_upper(&bitcode, "1_1_1_1_1_1_1_1_"));
+        assert!(bitmatch_upper(&bitcode, "0_0_0_0_0_0_0_0_"));
+        assert!(bitmatch_upper(&(bitcode << 1), "1_1_1_1_1_1_1_1_"));
+        assert!(!bitmatch_upper(&(bitcode << 1), "0_0_0_0_0_0_0_0_"));
+        assert!(!bitmatch_upper(&(bitcode << 8), "1_1_1_1_________"));
+        assert!(bitmatch_upper(&(bitcode << 8), "0_0_0_0_________"));
+        assert!(bitmatch_upper(&(bitcode << 9), "1_1_1_1_________"));
+        assert!(!bitmatch_upper(&(bitcode << 9), "0_0_0_0_________"));
     }
 
     #[test]
     fn test_bitmach_32_3() {
         let bitcode: u32 = 0b11111111101010100101010100000000;
-        assert_eq!(bitmatch_upper(&bitcode, "11111111"), true);
-        assert_eq!(bitmatch_lower(&bitcode, "11111111"), false);
-        assert_eq!(bitmatch_upper(&bitcode, "00000000"), false);
-        assert_eq!(bitmatch_lower(&bitcode, "00000000"), true);
+        assert!(bitmatch_upper(&bitcode, "11111111"));
+        assert!(!bitmatch_lower(&bitcode, "11111111"));
+        assert!(!bitmatch_upper(&bitcode, "00000000"));
+        assert!(bitmatch_lower(&bitcode, "00000000"));
     }
 
     #[test]
     fn test_check_bitcode_upper_1() {
         let bitcode: u32 = 0b11111111101010100101010100000000;
-        assert_eq!(check_bitcode_upper(&bitcode, "11111111", ""), true);
-        assert_eq!(check_bitcode_upper(&bitcode, "11111111", NO_COND), true);
-        assert_eq!(
-            check_bitcode_upper(&bitcode, "11111111", "1111111111"),
-            true
-        );
-        assert_eq!(
-            check_bitcode_upper(&bitcode, "11111111", "0000000000"),
-            true
-        );
-        assert_eq!(
-            check_bitcode_upper(&bitcode, "11111111", "1111111111|0000000000"),
-            true
-        );
-        assert_eq!(
-            check_bitcode_upper(&bitcode, "11111111", "11111111 11|00000000 00"),
-            true
-        );
-        assert_eq!(
-            check_bitcode_upper(&bitcode, "11111111", "1111111110"),
-            false
-        );
-        assert_eq!(
-            check_bitcode_upper(&bitcode, "11111111", "11111111 11|00000000 00|11111111 10"),
-            false
-        );
-        assert_eq!(
-            check_bitcode_upper(
-                &bitcode,
-                "11111111",
-                "  11111111 11|00000000 00|11111111 10  "
-            ),
-            false
-        );
+        assert!(check_bitcode_upper(&bitcode, "11111111", ""));
+        assert!(check_bitcode_upper(&bitcode, "11111111", NO_COND));
+        assert!(check_bitcode_upper(&bitcode, "11111111", "1111111111"));
+        assert!(check_bitcode_upper(&bitcode, "11111111", "0000000000"));
+        assert!(check_bitcode_upper(
+            &bitcode,
+            "11111111",
+            "1111111111|0000000000"
+        ));
+        assert!(check_bitcode_upper(
+            &bitcode,
+            "11111111",
+            "11111111 11|00000000 00"
+        ));
+        assert!(!check_bitcode_upper(&bitcode, "11111111", "1111111110"));
+        assert!(!check_bitcode_upper(
+            &bitcode,
+            "11111111",
+            "11111111 11|00000000 00|11111111 10"
+        ));
+        assert!(!check_bitcode_upper(
+            &bitcode,
+            "11111111",
+            "  11111111 11|00000000 00|11111111 10  "
+        ));
     }
 
     #[test]
@@ -472,7 +440,7 @@ mod tests {
                 assert_eq!(capture["captured"], bitcode);
             }
             Err(e) => {
-                assert_eq!(e, true);
+                assert!(e);
             }
         }
     }
@@ -491,7 +459,7 @@ mod tests {
                 assert_eq!(capture.get("captured"), Some(&bitcode));
             }
             Err(e) => {
-                assert_eq!(e, true);
+                assert!(e);
             }
         }
     }
@@ -508,7 +476,7 @@ mod tests {
                 assert_eq!(capture.get("captured"), Some(&0b1111));
             }
             Err(e) => {
-                assert_eq!(e, true);
+                assert!(e);
             }
         }
     }
@@ -525,7 +493,7 @@ mod tests {
                 assert_eq!(capture.get("captured"), Some(&bitcode));
             }
             Err(e) => {
-                assert_eq!(e, true);
+                assert!(e);
             }
         }
     }
@@ -542,7 +510,7 @@ mod tests {
                 assert_eq!(capture.get("captured"), Some(&bitcode));
             }
             Err(e) => {
-                assert_eq!(e, true);
+                assert!(e);
             }
         }
 
@@ -554,7 +522,7 @@ mod tests {
                 assert_eq!(capture.get("captured"), Some(&bitcode));
             }
             Err(e) => {
-                assert_eq!(e, true);
+                assert!(e);
             }
         }
 
@@ -1,11 +1,17 @@
+use std::collections::VecDeque;
+
 use crate::bitdecode::*;
-use crate::cpuflag::add_with_carry;
-use crate::cpuflag::ArmV6m;
-use crate::cpuflag::CalcFlags;
 use crate::debug_info::{b16_fmt, b32_fmt};
 use crate::device::SystemMap;
 use crate::device::SystemMapAccess;
+use crate::exception::*;
+use crate::injection::{Injection, Trigger};
 use crate::instruction::*;
+use crate::nvic::Nvic;
+use crate::scb::*;
+use crate::systick::SysTick;
+
+pub const EPSR_T: u32 = 0b1 << 24;
 
 pub struct CortexM0 {
     pub r: [u32; 13],
@@ -21,6 +27,8 @@ pub struct CortexM0 {
     pub ipsr: u32,
     pub epsr: u32,
 
+    pub exception_active: [bool; EXC_MAX],
+
     pub actlr: u32,
     pub cpuid: u32,
     pub icsr: u32,
@@ -49,13 +57,16 @@ impl Default for CortexM0 {
             apsr: 0,
             ipsr: 0,
             epsr: 0,
+
+            exception_active: [false; EXC_MAX],
+
             actlr: 0,
             cpuid: 0x410cc200,
             icsr: 0,
             vtor: 0,
             aircr: 0,
             scr: 0,
-            ccr: 0xffffffff,
+            ccr: 0x00000208,
             shpr2: 0,
             shpr3: 0,
             shcsr: 0,
@@ -64,9 +75,45 @@ impl Default for CortexM0 {
     }
 }
 
+// Cortex-M0 is implemented with either a single cycle or a 32 cycle multiplier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Multiplier {
+    Fast,
+    Small,
+}
+
+// faults are either taken as HardFault or stop the simulation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaultAction {
+    HardFault,
+    Stop,
+}
+
+// the processor locks up on a fault that can not be escalated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunState {
+    Running,
+    Stopped,
+    Lockup,
+}
+
+pub const LOCKUP_ADDRESS: u32 = 0xfffffffe;
+
+// number of executed instructions kept for the lockup report
+pub const TRACE_DEPTH: usize = 16;
+
 pub struct M0System {
     pub cpu: CortexM0,
     pub system_map: SystemMap,
+    pub multiplier: Multiplier,
+    pub fault_action: FaultAction,
+    pub state: RunState,
+    pub fault_chain: Vec<String>,
+    // SP selection and frame pointer stacked on the entry of HardFault
+    pub fault_frame: Option<(usize, u32)>,
+    pub trace: VecDeque<(u32, u16)>,
+    pub cycle_count: u64,
+    pub injections: Vec<Injection>,
 }
 
 impl M0System {
@@ -76,9 +123,142 @@ impl M0System {
             cpu: CortexM0 {
                 ..CortexM0::default()
             },
-            system_map: system_map,
+            system_map,
+            multiplier: Multiplier::Fast,
+            fault_action: FaultAction::HardFault,
+            state: RunState::Running,
+            fault_chain: Vec::new(),
+            fault_frame: None,
+            trace: VecDeque::with_capacity(TRACE_DEPTH),
+            cycle_count: 0,
+            injections: Vec::new(),
+        }
+    }
+
+    pub fn inject(&mut self, injection: Injection) {
+        self.injections.push(injection);
+    }
+
+    // pends the injected exceptions whose trigger has been reached
+    fn raise_injections(&mut self) {
+        let cycle_count: u64 = self.cycle_count;
+        let pc: u32 = self.cpu.pc;
+        let mut raised: Vec<usize> = Vec::new();
+        self.injections.retain(|injection| {
+            let reached: bool = match injection.trigger {
+                Trigger::Cycle(cycle) => cycle_count >= cycle,
+                Trigger::Pc(adrs) => pc == adrs,
+            };
+            if reached {
+                raised.push(injection.exception_number);
+            }
+            !reached
+        });
+        for exception_number in raised {
+            println!("\t INJECTED EXCEPTION: {}", exception_number);
+            set_exception_pending(self, exception_number);
         }
     }
+
+    // advances the time of the processor and the timers
+    pub fn tick(&mut self, cycles: u32) {
+        self.cycle_count += cycles as u64;
+        if self.system_map.systick.tick(cycles) {
+            set_exception_pending(self, EXC_SYSTICK);
+        }
+        self.raise_injections();
+    }
+
+    // fault chain, stacked registers and the last instructions before the lockup
+    pub fn lockup_report(&mut self) -> String {
+        let mut report: String = String::new();
+        report += "*LOCKUP REPORT --------------------------------------------------\n";
+        report += &format!("  pc:\t\t{:08x}\n", self.cpu.pc);
+        report += "  fault chain:\n";
+        for (n, fault) in self.fault_chain.iter().enumerate() {
+            report += &format!("    {}: {}\n", n, fault);
+        }
+        match self.fault_frame {
+            Some((spsel, frameptr)) => {
+                let sp_name: &str = if spsel == 0 { "msp" } else { "psp" };
+                report += &format!("  stacked registers ({} {:08x}):\n", sp_name, frameptr);
+                let names: [&str; 8] = ["r0", "r1", "r2", "r3", "r12", "lr", "pc", "xpsr"];
+                for (i, name) in names.iter().enumerate() {
+                    match self.system_map.read32(frameptr.wrapping_add(4 * i as u32)) {
+                        Ok(val) => report += &format!("    {}:\t{:08x}\n", name, val),
+                        Err(_) => report += &format!("    {}:\t--------\n", name),
+                    }
+                }
+            }
+            None => report += "  stacked registers: none\n",
+        }
+        report += "  last instructions:\n";
+        for (adrs, bytecode) in self.trace.iter() {
+            report += &format!("    {:08x}:\t{:04x}\n", adrs, bytecode);
+        }
+        report
+    }
+
+    // accesses from the processor: the SCB is backed by the registers of CortexM0
+    pub fn read8(&mut self, adrs: u32) -> Result<u8, String> {
+        if is_scb_mapped(adrs) {
+            return Err(format!(
+                "Error: read8(): can not access to {:08x} in SCB",
+                adrs
+            ));
+        }
+        self.system_map.read8(adrs)
+    }
+
+    pub fn read16(&mut self, adrs: u32) -> Result<u16, String> {
+        if is_scb_mapped(adrs) {
+            return Err(format!(
+                "Error: read16(): can not access to {:08x} in SCB",
+                adrs
+            ));
+        }
+        self.system_map.read16(adrs)
+    }
+
+    pub fn read32(&mut self, adrs: u32) -> Result<u32, String> {
+        if is_scb_mapped(adrs) {
+            return scb_read32(self, adrs).ok_or(format!(
+                "Error: read32(): can not access to {:08x} in SCB",
+                adrs
+            ));
+        }
+        self.system_map.read32(adrs)
+    }
+
+    pub fn write8(&mut self, adrs: u32, val: u8) -> Result<(), String> {
+        if is_scb_mapped(adrs) {
+            return Err(format!(
+                "Error: write8(): can not access to {:08x} in SCB",
+                adrs
+            ));
+        }
+        self.system_map.write8(adrs, val)
+    }
+
+    pub fn write16(&mut self, adrs: u32, val: u16) -> Result<(), String> {
+        if is_scb_mapped(adrs) {
+            return Err(format!(
+                "Error: write16(): can not access to {:08x} in SCB",
+                adrs
+            ));
+        }
+        self.system_map.write16(adrs, val)
+    }
+
+    pub fn write32(&mut self, adrs: u32, val: u32) -> Result<(), String> {
+        if is_scb_mapped(adrs) {
+            return scb_write32(self, adrs, val).ok_or(format!(
+                "Error: write32(): can not access to {:08x} in SCB",
+                adrs
+            ));
+        }
+        self.system_map.write32(adrs, val)
+    }
 }
 
 pub trait SystemCtrl {
@@ -93,9 +273,26 @@ impl SystemCtrl for M0System {
         self.cpu = CortexM0 {
             ..CortexM0::default()
         };
-        self.cpu.sp[self.cpu.ctrl_spsel] =
-            self.system_map.read32(self.cpu.vtor).unwrap() & 0xfffffffc;
-        self.cpu.pc = self.system_map.read32(self.cpu.vtor + 4).unwrap() & 0xfffffffe;
+        self.system_map.nvic = Nvic::default();
+        self.system_map.systick = SysTick::default();
+        self.state = RunState::Running;
+        self.fault_chain.clear();
+        self.fault_frame = None;
+        // a failed vector fetch leaves EPSR.T cleared, the first instruction faults
+        match self.system_map.read32(self.cpu.vtor) {
+            Ok(sp) => self.cpu.sp[self.cpu.ctrl_spsel] = sp & 0xfffffffc,
+            Err(e) => println!("\t BUS ERROR: {}", e),
+        }
+        match self.system_map.read32(self.cpu.vtor + 4) {
+            Ok(reset_vector) => {
+                self.cpu.pc = reset_vector & 0xfffffffe;
+                self.cpu.epsr = (reset_vector & 0b1) << 24;
+            }
+            Err(e) => {
+                println!("\t BUS ERROR: {}", e);
+                self.cpu.epsr = 0;
+            }
+        }
     }
 
     fn dump(&self) {
@@ -119,12 +316,41 @@ impl SystemCtrl for M0System {
     }
 
     fn execute(&mut self) -> u32 {
-        get_thumb_instruction(self)
+        if self.state != RunState::Running {
+            return 0;
+        }
+        self.raise_injections();
+        // an exception entry already counts the cycles of its stacking
+        let start: u64 = self.cycle_count;
+        let cycles: u32 = match check_exception(self) {
+            Some(cycles) => cycles,
+            None => get_thumb_instruction(self),
+        };
+        if cycles == 0 && self.state == RunState::Running {
+            self.state = RunState::Stopped;
+        }
+        if self.cpu.aircr & AIRCR_SYSRESETREQ != 0 {
+            self.reset();
+            return cycles;
+        }
+        let elapsed: u32 = (self.cycle_count - start) as u32;
+        self.tick(cycles.saturating_sub(elapsed));
+        cycles
     }
 }
 
 fn get_thumb_instruction(system: &mut M0System) -> u32 {
-    let bytecode: u16 = system.system_map.read16(system.cpu.pc).unwrap();
+    if system.cpu.epsr & EPSR_T == 0 {
+        return invalid_state(system);
+    }
+    let bytecode: u16 = match system.read16(system.cpu.pc) {
+        Ok(bytecode) => bytecode,
+        Err(e) => return bus_error(e, system),
+    };
+    if system.trace.len() == TRACE_DEPTH {
+        system.trace.pop_front();
+    }
+    system.trace.push_back((system.cpu.pc, bytecode));
 
     println!(
         "adrs:{:08x}\t{:04x}({})",
@@ -233,47 +459,103 @@ fn get_thumb_instruction(system: &mut M0System) -> u32 {
     decode_error(bytecode, system)
 }
 
-// 000 opecode[2] imm[4] Rm[3] Rd[3]
+// 000 opecode[2] imm[5] Rm[3] Rd[3]
 fn shift_by_immediate_move_register(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t shift by immediate, move register");
-    not_impremented(system)
+    let opcode: u16 = (bytecode >> 11) & 0b11;
+    let imm: u16 = (bytecode >> 6) & 0b11111;
+    match opcode {
+        0b00 => {
+            if imm == 0 {
+                mov_register(bytecode, system)
+            } else {
+                lsl_immediate(bytecode, system)
+            }
+        }
+        0b01 => lsr_immediate(bytecode, system),
+        0b10 => asr_immediate(bytecode, system),
+        _ => found_bug(bytecode, system),
+    }
 }
 
 // 000110 opc[1] Rm[3] Rn[3] Rd[3]
 fn add_substract_register(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Add/substract register");
-    not_impremented(system)
+    let opc: u16 = bytecode & (0b1 << 9);
+    if opc == 0 {
+        add_register(bytecode, system)
+    } else {
+        sub_register(bytecode, system)
+    }
 }
 
 // 000111 opc[1] imm[3] Rn[3] Rd[3]
 fn add_substract_immediate(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Add/substract immediate");
-    not_impremented(system)
+    let opc: u16 = bytecode & (0b1 << 9);
+    if opc == 0 {
+        add_immediate(bytecode, system)
+    } else {
+        sub_immediate(bytecode, system)
+    }
 }
 
 // 001 opecode[2] Rdn[3] imm[8]
 fn add_substract_compare_move_immediate(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Add/Sub/Compare/Move immediate");
-    not_impremented(system)
+    let opcode: u16 = (bytecode >> 11) & 0b11;
+    match opcode {
+        0b00 => mov_immediate(bytecode, system),
+        0b01 => cmp_immediate(bytecode, system),
+        0b10 => add_immediate(bytecode, system),
+        _ => sub_immediate(bytecode, system),
+    }
 }
 
 // 010000 opecode[4] Rm[3] Rdn[3]
 fn data_processing_register(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Data-processing register");
-    not_impremented(system)
+    let opcode: u16 = (bytecode >> 6) & 0b1111;
+    match opcode {
+        0b0000 => and_register(bytecode, system),
+        0b0001 => eor_register(bytecode, system),
+        0b0010 => lsl_register(bytecode, system),
+        0b0011 => lsr_register(bytecode, system),
+        0b0100 => asr_register(bytecode, system),
+        0b0101 => adc_register(bytecode, system),
+        0b0110 => sbc_register(bytecode, system),
+        0b0111 => ror_register(bytecode, system),
+        0b1000 => tst_register(bytecode, system),
+        0b1001 => rsb_immediate(bytecode, system),
+        0b1010 => cmp_register(bytecode, system),
+        0b1011 => cmn_register(bytecode, system),
+        0b1100 => orr_register(bytecode, system),
+        0b1101 => mul(bytecode, system),
+        0b1110 => bic_register(bytecode, system),
+        _ => mvn_register(bytecode, system),
+    }
 }
 
 // 010001 opecode[2] DN[1] Rm[3] Rdn[3]
 fn special_data_processing(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Special data processing");
-    not_impremented(system)
+    match (bytecode >> 8) & 0b11 {
+        0b00 => add_high_register(bytecode, system),
+        0b01 => cmp_high_register(bytecode, system),
+        0b10 => mov_high_register(bytecode, system),
+        _ => bx(bytecode, system),
+    }
 }
 
 // 01000111 L[1]  Rm[3] 000
 fn branch_exchange_instruction_set(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.250
     println!("\t Branch/exchange instruction set");
-    bx(bytecode, system)
+    if bytecode & (0b1 << 7) != 0 {
+        blx(bytecode, system)
+    } else {
+        bx(bytecode, system)
+    }
 }
 
 // 01001 Rd[3] PC-relative-imm[8]
@@ -283,13 +565,16 @@ fn load_from_literal_pool(bytecode: u16, system: &mut M0System) -> u32 {
     let regnum: usize = ((bytecode >> 8) & 0b111) as usize;
     let imm: u16 = bytecode & 0b11111111;
     let imm32: u32 = (imm << 2) as u32;
-    let pc_aligned: u32 = system.cpu.pc & 0xfffffffc;
-    let load_address: u32 = pc_aligned + imm32;
+    let pc_aligned: u32 = system.cpu.pc.wrapping_add(4) & 0xfffffffc;
+    let load_address: u32 = pc_aligned.wrapping_add(imm32);
     println!(
         "\t\t ldr  r{}, [pc, #{}]  ;b load from {:08x}",
         regnum, imm32, load_address
     );
-    let load_data: u32 = system.system_map.read32(load_address).unwrap();
+    let load_data: u32 = match system.read32(load_address) {
+        Ok(data) => data,
+        Err(e) => return bus_error(e, system),
+    };
     println!("\t Read data:0x{:08x}", load_data);
     match regnum {
         15 => {
@@ -315,67 +600,114 @@ fn load_from_literal_pool(bytecode: u16, system: &mut M0System) -> u32 {
 // 0101 opecode[3] Rm[3] Rn[3] Rd[3]
 fn load_store_register_offset(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Load/Store register offset");
-    not_impremented(system)
+    let opcode: u16 = (bytecode >> 9) & 0b111;
+    match opcode {
+        0b000 => str_register(bytecode, system),
+        0b001 => strh_register(bytecode, system),
+        0b010 => strb_register(bytecode, system),
+        0b011 => ldrsb(bytecode, system),
+        0b100 => ldr_register(bytecode, system),
+        0b101 => ldrh_register(bytecode, system),
+        0b110 => ldrb_register(bytecode, system),
+        _ => ldrsh(bytecode, system),
+    }
 }
 
 // 01100 imm[5] Rn[3] Rd[3]
 fn store_word_immediate_offset(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferenceManual.pdf p.421
     println!("\t Store word immediate offset");
-    let imm: u16 = (bytecode >> 6) & 0b11111;
-    let regnum_base: usize = ((bytecode >> 3) & 0b111) as usize;
-    let regnum_target: usize = (bytecode & 0b111) as usize;
-
-    println!("\t\t str r{}, [r{}, #{}]", regnum_target, regnum_base, imm);
-    not_impremented(system)
+    str_immediate(bytecode, system)
 }
 
 // 01101 imm[5] Rn[3] Rd[3]
 fn loade_word_immediate_offset(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Load word immediate offset");
-    not_impremented(system)
+    ldr_immediate(bytecode, system)
 }
 
 // 01110 imm[5] Rn[3] Rd[3]
 fn store_byte_immediate_offset(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Store byte immediate offset");
-    not_impremented(system)
+    strb_immediate(bytecode, system)
 }
 
 // 01111 imm[5] Rn[3] Rd[3]
 fn loade_byte_immediate_offset(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Load byte immediate offset");
-    not_impremented(system)
+    ldrb_immediate(bytecode, system)
 }
 
 //10000 imm[5]  Rn[3] Rd[3]
 fn store_halfward_immediate_offset(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Store halfword immediate offset");
-    not_impremented(system)
+    strh_immediate(bytecode, system)
 }
 
 //10001 imm[5]  Rn[3] Rd[3]
 fn load_halfward_immediate_offset(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Load halfword immediate offset");
-    not_impremented(system)
+    ldrh_immediate(bytecode, system)
 }
 
 // 10010 Rd[3] SP-relative-imm[8]
 fn store_to_stack(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STR (immediate)
     println!("\t Store to stack");
-    not_impremented(system)
+    let regnum: usize = ((bytecode >> 8) & 0b111) as usize;
+    let imm: u16 = bytecode & 0b11111111;
+    let imm32: u32 = (imm << 2) as u32;
+    let store_address: u32 = system.cpu.sp[system.cpu.ctrl_spsel].wrapping_add(imm32);
+    println!(
+        "\t\t str  r{}, [sp, #{}]  ; store to {:08x}",
+        regnum, imm32, store_address
+    );
+    if store_address & 0b11 != 0 {
+        return unaligned_access(store_address, system);
+    }
+    if let Err(e) = system.write32(store_address, system.cpu.r[regnum]) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
 }
 
 // 10011 Rd[3] SP-relative-imm[8]
 fn load_from_stack(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDR (immediate)
     println!("\t Load from stack");
-    not_impremented(system)
+    let regnum: usize = ((bytecode >> 8) & 0b111) as usize;
+    let imm: u16 = bytecode & 0b11111111;
+    let imm32: u32 = (imm << 2) as u32;
+    let load_address: u32 = system.cpu.sp[system.cpu.ctrl_spsel].wrapping_add(imm32);
+    println!(
+        "\t\t ldr  r{}, [sp, #{}]  ; load from {:08x}",
+        regnum, imm32, load_address
+    );
+    if load_address & 0b11 != 0 {
+        return unaligned_access(load_address, system);
+    }
+    match system.read32(load_address) {
+        Ok(data) => system.cpu.r[regnum] = data,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
 }
 
 // 10100 Rd[3] imm[8]
 fn add_to_pc(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ADR
     println!("\t Add to PC");
-    not_impremented(system)
+    let regnum: usize = ((bytecode >> 8) & 0b111) as usize;
+    let imm: u16 = bytecode & 0b11111111;
+    let imm32: u32 = (imm << 2) as u32;
+    let pc_aligned: u32 = system.cpu.pc.wrapping_add(4) & 0xfffffffc;
+    let address: u32 = pc_aligned.wrapping_add(imm32);
+    println!("\t\t adr  r{}, #{}  ; {:08x}", regnum, imm32, address);
+    system.cpu.r[regnum] = address;
+    system.cpu.pc += 2;
+    1
 }
 
 // 10101 Rd[3] imm[8]
@@ -384,9 +716,7 @@ fn add_to_sp(bytecode: u16, system: &mut M0System) -> u32 {
     let imm: u16 = bytecode & 0b11111111;
     let imm32: u32 = (imm << 2) as u32;
     println!("\t\t add  r{}, sp, #{}", regnum, imm32);
-    let r: ArmV6m = add_with_carry(system.cpu.sp[system.cpu.ctrl_spsel], imm32, 0);
-    system.cpu.r[regnum] = r.result;
-    system.cpu.apsr = r.flags_to_apsr();
+    system.cpu.r[regnum] = system.cpu.sp[system.cpu.ctrl_spsel].wrapping_add(imm32);
     system.cpu.pc += 2;
     1
 }
@@ -396,7 +726,7 @@ fn add_to_sp(bytecode: u16, system: &mut M0System) -> u32 {
 fn miscellaneous(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Miscellaneous 16-bit instractions {}", b16_fmt(bytecode));
     let bit_11_08 = (bytecode >> 8) & 0xf;
-    let bit_07_04 = (bytecode >> 4) & 0xf;
+    let _bit_07_04 = (bytecode >> 4) & 0xf;
     let bit_03_00 = bytecode & 0xf;
     match bit_11_08 {
         0b0000 => adjust_stack_pointer(bytecode, system),
@@ -405,13 +735,15 @@ fn miscellaneous(bytecode: u16, system: &mut M0System) -> u32 {
         0b1001 | 0b1011 => cbnz(bytecode, system),
         0b0100 | 0b0101 => push(bytecode, system),
         0b1100 | 0b1101 => pop(bytecode, system),
+        0b1010 => reverse_bytes(bytecode, system),
         0b1110 => bkpt(bytecode, system),
         0b1111 => match bit_03_00 {
             0b0000 => nop_compatible_hints(bytecode, system),
             _ => it(bytecode, system),
         },
-        0b0110 => match bit_03_00 {
-            _ => not_impremented(system),
+        0b0110 => match bytecode & 0b11101111 {
+            0b01100010 => cps(bytecode, system),
+            _ => undefined_instruction(bytecode, system),
         },
         _ => unpredicable(system),
     }
@@ -440,15 +772,13 @@ fn adjust_stack_pointer(bytecode: u16, system: &mut M0System) -> u32 {
     if opc == 0 {
         // Ref: Thumb-2SupplementReferencemanual.pdf p.108
         println!("\t\t add  sp, sp, #{}", imm32);
-        let r: ArmV6m = add_with_carry(system.cpu.sp[system.cpu.ctrl_spsel], imm32, 0);
-        system.cpu.sp[system.cpu.ctrl_spsel] = r.result;
-        system.cpu.apsr = r.flags_to_apsr();
+        system.cpu.sp[system.cpu.ctrl_spsel] =
+            system.cpu.sp[system.cpu.ctrl_spsel].wrapping_add(imm32);
     } else {
         // Ref: Thumb-2SupplementReferencemanual.pdf p.453
         println!("\t\t sub  sp, sp, #-{}", imm32);
-        let r: ArmV6m = add_with_carry(system.cpu.sp[system.cpu.ctrl_spsel], !imm32, 1);
-        system.cpu.sp[system.cpu.ctrl_spsel] = r.result;
-        system.cpu.apsr = r.flags_to_apsr();
+        system.cpu.sp[system.cpu.ctrl_spsel] =
+            system.cpu.sp[system.cpu.ctrl_spsel].wrapping_sub(imm32);
     }
     system.cpu.pc += 2;
     1
@@ -457,43 +787,47 @@ fn adjust_stack_pointer(bytecode: u16, system: &mut M0System) -> u32 {
 // 1011 0010 opc[1] imm[7]
 fn sign_zero_extend(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Sign/Zero extend");
-    not_impremented(system)
+    match (bytecode >> 6) & 0b11 {
+        0b00 => sxth(bytecode, system),
+        0b01 => sxtb(bytecode, system),
+        0b10 => uxth(bytecode, system),
+        _ => uxtb(bytecode, system),
+    }
+}
+
+// 1011 1010 opc[2] Rm[3] Rd[3]
+fn reverse_bytes(bytecode: u16, system: &mut M0System) -> u32 {
+    println!("\t Reverse bytes");
+    match (bytecode >> 6) & 0b11 {
+        0b00 => rev(bytecode, system),
+        0b01 => rev16(bytecode, system),
+        0b11 => revsh(bytecode, system),
+        _ => undefined_instruction(bytecode, system),
+    }
 }
 
 //11000 Rn[3] imm[8]
 fn store_multiple(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Store multiple");
-    not_impremented(system)
+    stm(bytecode, system)
 }
 
 //11001 Rn[3] imm[8]
 fn load_multiple(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Load multiple");
-    not_impremented(system)
+    ldm(bytecode, system)
 }
 
 // 1101 cond[2] imm[8]
 fn conditional_branch(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Conditional branch");
-    not_impremented(system)
-}
-
-// 11011110 x[8]
-fn undefined_instruction(bytecode: u16, system: &mut M0System) -> u32 {
-    println!("\t (Undefined Instruction (32bit) {:016b})", bytecode);
-    not_impremented(system)
-}
-
-// 1111100 x[2] 111 x[20]
-fn undefined_instruction_32(bytecode32: u32, system: &mut M0System) -> u32 {
-    println!("\t (Undefined Instruction {:032b})", bytecode32);
-    not_impremented(system)
+    b_16(bytecode, system)
 }
 
 // 11011111 imm[8]
 fn service_call(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t Service call");
-    not_impremented(system)
+    svc(bytecode, system)
 }
 
 // 11100 imm[11]
@@ -506,7 +840,10 @@ fn unconditional_branch(bytecode: u16, system: &mut M0System) -> u32 {
 fn instruction_32bit(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.164
     println!("\t 32-bit instruction (1111)");
-    let bytecode_lower: u16 = system.system_map.read16(system.cpu.pc + 2).unwrap();
+    let bytecode_lower: u16 = match system.read16(system.cpu.pc + 2) {
+        Ok(bytecode_lower) => bytecode_lower,
+        Err(e) => return bus_error(e, system),
+    };
     let bytecode32 = (bytecode as u32) << 16 | bytecode_lower as u32;
     println!("\t bytecode 32bit {:08x}", bytecode32);
     // op1 == 0b01
@@ -619,7 +956,7 @@ fn load_and_store_multiple(bytecode32: u32, system: &mut M0System) -> u32 {
     //     _ => not_impremented(system),
     // }
 
-    not_impremented(system)
+    undefined_instruction_32(bytecode32, system)
 }
 
 fn load_and_store_double_exclusive_table_branch(bytecode32: u32, system: &mut M0System) -> u32 {
@@ -670,23 +1007,31 @@ fn data_processing_plain_binary_immediate(bytecode32: u32, system: &mut M0System
 fn branch_miscellaneous(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.169
     println!("\t Branch and micscellaneous control");
-    let field = parse_bit_u(&bytecode32, "111 10 aaaaaaa **** 1 bbb").unwrap();
+    let field = parse_bit_u(&bytecode32, "111 10 aaaaaaa ____ 1 bbb").unwrap();
     let op1 = field["b"];
     let op = field["a"];
     let sub_bitcode: u32 = field["captured"];
 
     println!(
-        "\t sub_bitcode:{:010b} op1: {:03b} op1:{:07b}",
+        "\t sub_bitcode:{:010b} op1:{:03b} op:{:07b}",
         sub_bitcode, op1, op
     );
 
     // bitcode_l_ex!(sub_bitcode, "011100* 0*0", "*111*** 0*0", b_32(bytecode32, system));
-    // bitcode_l!(sub_bitcode, "011100* 0*0", msr_32(bytecode32, system));
+    bitcode_l!(sub_bitcode, "011100* 0*0", msr_32(bytecode32, system));
     // bitcode_l!(sub_bitcode, "0111010 0*0", hint_32(bytecode32, system));
-    // bitcode_l!(sub_bitcode, "0111011 0*0", miscellaneous_control_32(bytecode32, system));
-    // bitcode_l!(sub_bitcode, "011111* 0*0", mrs_32(bytecode32, system));
-    // bitcode_l!(sub_bitcode, "1111111 010", undefined_instruction_32(bytecode32, system));
-    // bitcode_l!(sub_bitcode, "******* 0*1", b_32(bytecode32, system));
+    bitcode_l!(
+        sub_bitcode,
+        "0111011 0*0",
+        miscellaneous_control_32(bytecode32, system)
+    );
+    bitcode_l!(sub_bitcode, "011111* 0*0", mrs_32(bytecode32, system));
+    bitcode_l!(
+        sub_bitcode,
+        "1111111 010",
+        undefined_instruction_32(bytecode32, system)
+    );
+    bitcode_l!(sub_bitcode, "******* 0*1", b_32(bytecode32, system));
     bitcode_l!(sub_bitcode, "******* 1*1", bl_32(bytecode32, system));
 
     undefined_instruction_32(bytecode32, system)
@@ -694,8 +1039,14 @@ fn branch_miscellaneous(bytecode32: u32, system: &mut M0System) -> u32 {
 
 fn miscellaneous_control_32(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.170
-    println!("\t Hint instructions (32bit)");
-    not_impremented(system)
+    println!("\t Miscellaneous control instructions (32bit)");
+    let field = parse_bit_u(&bytecode32, "11110 0 111 01 1 1111 10 0 0 1111 oooo ****").unwrap();
+    match field["o"] {
+        0b0100 => dsb_32(bytecode32, system),
+        0b0101 => dmb_32(bytecode32, system),
+        0b0110 => isb_32(bytecode32, system),
+        _ => undefined_instruction_32(bytecode32, system),
+    }
 }
 
 fn store_single_data_item(bytecode32: u32, system: &mut M0System) -> u32 {
@@ -713,33 +1064,7 @@ fn load_byte_memory_hints(bytecode32: u32, system: &mut M0System) -> u32 {
 fn load_harfword_memory_hints(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.174
     println!("\t Load harfword, memory hints");
-    let field = parse_bit_u(&bytecode32, "111 1100 aa 011 nnnn tttt bbbbbb").unwrap();
-    let op1: u32 = field["a"];
-    let op2: u32 = field["b"];
-    let rn: u32 = field["b"];
-    let rt: u32 = field["t"];
-    let sub_bitcode: u32 = field["captured"];
-    println!("\t op1:{:02b} op2:{:06b} Rn:{} Rt:{}", op1, op2, rn, rt);
-
-    if rt == 0b1111 {
-        bitcode_l_ex!(
-            sub_bitcode,
-            "00 000000 ****",
-            "00 000000 1111",
-            nop_32(bytecode32, system)
-        );
-    } else {
-        bitcode_l!(
-            sub_bitcode,
-            "0* ****** 1111 ****",
-            ldrh_32(bytecode32, system)
-        );
-    }
-    0
-}
-
-fn ldrh_32(bytecode32: u32, system: &mut M0System) -> u32 {
-    0
+    undefined_instruction_32(bytecode32, system)
 }
 
 fn load_word(bytecode32: u32, system: &mut M0System) -> u32 {
@@ -751,24 +1076,24 @@ fn load_word(bytecode32: u32, system: &mut M0System) -> u32 {
 fn data_processing_register_32(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.181
     println!("\t Data procerssing register (32bit)");
-    not_impremented(system)
+    undefined_instruction_32(bytecode32, system)
 }
 
 fn multiply_accumlate_absolutre_difference(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.186
     println!("\t Multiply, multiply accumulate, and absolute difference (32bit)");
-    not_impremented(system)
+    undefined_instruction_32(bytecode32, system)
 }
 
 fn long_multiply_accumlate_divide(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.187
     println!("\t Long multiply, long multiply accumulate, and divide (32bit)");
-    not_impremented(system)
+    undefined_instruction_32(bytecode32, system)
 }
 
 fn coprocessor_instructions(bytecode32: u32, system: &mut M0System) -> u32 {
     println!("\t Coprocessor (32bit)");
-    not_impremented(system)
+    undefined_instruction_32(bytecode32, system)
 }
 
 fn decode_error(bytecode: u16, system: &mut M0System) -> u32 {
@@ -780,3 +1105,1525 @@ fn found_bug(bytecode: u16, system: &mut M0System) -> u32 {
     println!("\t (SYSTEM_ERROR:FOUND BUG):{:08b}", bytecode);
     unpredicable(system)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{DeviceMapping, MemoryMappedDevice};
+    use crate::systick::{CSR_ENABLE, CSR_TICKINT};
+
+    const RAMSIZE: usize = 0x1000;
+
+    fn test_system() -> M0System {
+        let ram: MemoryMappedDevice = MemoryMappedDevice {
+            name: "RAM".to_string(),
+            data: Box::new([0; RAMSIZE]),
+            mapping: DeviceMapping {
+                adrs: 0,
+                size: RAMSIZE,
+            },
+            readable: true,
+            writable: true,
+        };
+        let mut system_map: SystemMap = SystemMap::new();
+        system_map.register_device(ram);
+        let mut system: M0System = M0System::new(system_map);
+        system.cpu.pc = 0x100;
+        system.cpu.epsr = EPSR_T;
+        system
+    }
+
+    fn execute16(system: &mut M0System, bytecode: u16) -> u32 {
+        let pc: u32 = system.cpu.pc;
+        system.system_map.write16(pc, bytecode).unwrap();
+        system.execute()
+    }
+
+    fn execute32(system: &mut M0System, bytecode32: u32) -> u32 {
+        let pc: u32 = system.cpu.pc;
+        system
+            .system_map
+            .write16(pc, (bytecode32 >> 16) as u16)
+            .unwrap();
+        system
+            .system_map
+            .write16(pc + 2, bytecode32 as u16)
+            .unwrap();
+        system.execute()
+    }
+
+    #[test]
+    fn test_data_processing_logical() {
+        let mut system: M0System = test_system();
+        system.cpu.apsr = 0b0011 << 28;
+        system.cpu.r[0] = 0xff00ff00;
+        system.cpu.r[1] = 0x0ff00ff0;
+
+        // ands r0, r1
+        assert_eq!(execute16(&mut system, 0x4008), 1);
+        assert_eq!(system.cpu.r[0], 0x0f000f00);
+        assert_eq!(system.cpu.pc, 0x102);
+        // C and V are not changed by logical operations
+        assert_eq!(system.cpu.apsr, 0b0011 << 28);
+
+        // eors r0, r0
+        execute16(&mut system, 0x4040);
+        assert_eq!(system.cpu.r[0], 0);
+        assert_eq!(system.cpu.apsr, 0b0111 << 28);
+
+        // mvns r2, r0
+        execute16(&mut system, 0x43c2);
+        assert_eq!(system.cpu.r[2], 0xffffffff);
+        assert_eq!(system.cpu.apsr, 0b1011 << 28);
+
+        // bics r2, r1
+        execute16(&mut system, 0x438a);
+        assert_eq!(system.cpu.r[2], 0xf00ff00f);
+
+        // orrs r0, r1
+        execute16(&mut system, 0x4308);
+        assert_eq!(system.cpu.r[0], 0x0ff00ff0);
+
+        // tst r0, r2
+        execute16(&mut system, 0x4210);
+        assert_eq!(system.cpu.r[0], 0x0ff00ff0);
+        assert_eq!(system.cpu.apsr, 0b0111 << 28);
+    }
+
+    #[test]
+    fn test_data_processing_shift_register() {
+        let mut system: M0System = test_system();
+        system.cpu.r[0] = 0x80000001;
+        system.cpu.r[1] = 1;
+
+        // lsls r0, r1
+        execute16(&mut system, 0x4088);
+        assert_eq!(system.cpu.r[0], 0x00000002);
+        assert_eq!(system.cpu.apsr, 0b0010 << 28);
+
+        // lsrs r0, r1
+        system.cpu.r[0] = 0x80000001;
+        execute16(&mut system, 0x40c8);
+        assert_eq!(system.cpu.r[0], 0x40000000);
+        assert_eq!(system.cpu.apsr, 0b0010 << 28);
+
+        // asrs r0, r1
+        system.cpu.r[0] = 0x80000000;
+        execute16(&mut system, 0x4108);
+        assert_eq!(system.cpu.r[0], 0xc0000000);
+        assert_eq!(system.cpu.apsr, 0b1000 << 28);
+
+        // rors r0, r1
+        system.cpu.r[0] = 0x00000001;
+        execute16(&mut system, 0x41c8);
+        assert_eq!(system.cpu.r[0], 0x80000000);
+        assert_eq!(system.cpu.apsr, 0b1010 << 28);
+
+        // shift by 0 keeps the carry flag
+        system.cpu.r[1] = 0x100;
+        execute16(&mut system, 0x4088);
+        assert_eq!(system.cpu.r[0], 0x80000000);
+        assert_eq!(system.cpu.apsr, 0b1010 << 28);
+
+        // lsls by 32 moves bit 0 into the carry flag
+        system.cpu.r[0] = 0x00000001;
+        system.cpu.r[1] = 32;
+        execute16(&mut system, 0x4088);
+        assert_eq!(system.cpu.r[0], 0);
+        assert_eq!(system.cpu.apsr, 0b0110 << 28);
+
+        // asrs by more than 32 fills with the sign bit
+        system.cpu.r[0] = 0x80000000;
+        system.cpu.r[1] = 40;
+        execute16(&mut system, 0x4108);
+        assert_eq!(system.cpu.r[0], 0xffffffff);
+        assert_eq!(system.cpu.apsr, 0b1010 << 28);
+    }
+
+    #[test]
+    fn test_data_processing_mul_cycles() {
+        let mut system: M0System = test_system();
+        system.cpu.r[0] = 3;
+        system.cpu.r[1] = 5;
+
+        // muls r0, r1, r0
+        assert_eq!(execute16(&mut system, 0x4348), 1);
+        assert_eq!(system.cpu.r[0], 15);
+
+        system.multiplier = Multiplier::Small;
+        assert_eq!(execute16(&mut system, 0x4348), 32);
+        assert_eq!(system.cpu.r[0], 75);
+    }
+
+    #[test]
+    fn test_data_processing_mul() {
+        let mut system: M0System = test_system();
+        system.cpu.r[2] = 0x10000;
+        system.cpu.r[3] = 0x10001;
+
+        // muls r3, r2, r3
+        execute16(&mut system, 0x4353);
+        assert_eq!(system.cpu.r[3], 0x00010000);
+        assert_eq!(system.cpu.apsr, 0);
+
+        system.cpu.r[2] = 0xffffffff;
+        execute16(&mut system, 0x4353);
+        assert_eq!(system.cpu.r[3], 0xffff0000);
+        assert_eq!(system.cpu.apsr, 0b1000 << 28);
+    }
+
+    #[test]
+    fn test_shift_by_immediate_move_register() {
+        let mut system: M0System = test_system();
+        system.cpu.r[1] = 0x80000003;
+
+        // lsls r0, r1, #1
+        execute16(&mut system, 0x0048);
+        assert_eq!(system.cpu.r[0], 0x00000006);
+        assert_eq!(system.cpu.apsr, 0b0010 << 28);
+
+        // lsrs r0, r1, #2
+        execute16(&mut system, 0x0888);
+        assert_eq!(system.cpu.r[0], 0x20000000);
+        assert_eq!(system.cpu.apsr, 0b0010 << 28);
+
+        // lsrs r0, r1, #32
+        execute16(&mut system, 0x0808);
+        assert_eq!(system.cpu.r[0], 0);
+        assert_eq!(system.cpu.apsr, 0b0110 << 28);
+
+        // asrs r0, r1, #32
+        execute16(&mut system, 0x1008);
+        assert_eq!(system.cpu.r[0], 0xffffffff);
+        assert_eq!(system.cpu.apsr, 0b1010 << 28);
+
+        // asrs r0, r1, #1
+        execute16(&mut system, 0x1048);
+        assert_eq!(system.cpu.r[0], 0xc0000001);
+        assert_eq!(system.cpu.apsr, 0b1010 << 28);
+
+        // movs r2, r1 (C and V are not changed)
+        system.cpu.apsr = 0b0011 << 28;
+        execute16(&mut system, 0x000a);
+        assert_eq!(system.cpu.r[2], 0x80000003);
+        assert_eq!(system.cpu.apsr, 0b1011 << 28);
+        assert_eq!(system.cpu.pc, 0x10c);
+    }
+
+    #[test]
+    fn test_add_substract() {
+        let mut system: M0System = test_system();
+        system.cpu.r[1] = 100;
+        system.cpu.r[2] = 30;
+
+        // adds r0, r1, r2
+        execute16(&mut system, 0x1888);
+        assert_eq!(system.cpu.r[0], 130);
+        // subs r3, r1, r2
+        execute16(&mut system, 0x1a8b);
+        assert_eq!(system.cpu.r[3], 70);
+        // adds r4, r1, #7
+        execute16(&mut system, 0x1dcc);
+        assert_eq!(system.cpu.r[4], 107);
+        // subs r5, r1, #7
+        execute16(&mut system, 0x1fcd);
+        assert_eq!(system.cpu.r[5], 93);
+        // adds r1, #255
+        execute16(&mut system, 0x31ff);
+        assert_eq!(system.cpu.r[1], 355);
+        // subs r2, #31
+        execute16(&mut system, 0x3a1f);
+        assert_eq!(system.cpu.r[2], 0xffffffff);
+        assert_eq!(system.cpu.pc, 0x10c);
+    }
+
+    #[test]
+    fn test_move_compare_immediate() {
+        let mut system: M0System = test_system();
+        system.cpu.apsr = 0b1011 << 28;
+
+        // movs r7, #0x80 (C and V are not changed)
+        execute16(&mut system, 0x2780);
+        assert_eq!(system.cpu.r[7], 0x80);
+        assert_eq!(system.cpu.apsr, 0b0011 << 28);
+
+        // movs r6, #0
+        execute16(&mut system, 0x2600);
+        assert_eq!(system.cpu.r[6], 0);
+        assert_eq!(system.cpu.apsr, 0b0111 << 28);
+
+        // cmp r7, #0x80 (result is discarded)
+        execute16(&mut system, 0x2f80);
+        assert_eq!(system.cpu.r[7], 0x80);
+        assert_eq!(system.cpu.apsr, 0b0110 << 28);
+
+        // cmp r6, #1
+        execute16(&mut system, 0x2e01);
+        assert_eq!(system.cpu.apsr, 0b1000 << 28);
+        assert_eq!(system.cpu.pc, 0x108);
+    }
+
+    #[test]
+    fn test_data_processing_arithmetic() {
+        let mut system: M0System = test_system();
+        system.cpu.r[0] = 0xffffffff;
+        system.cpu.r[1] = 1;
+
+        // adcs r0, r1 (C=0)
+        execute16(&mut system, 0x4148);
+        assert_eq!(system.cpu.r[0], 0);
+        assert_eq!(system.cpu.apsr, 0b0110 << 28);
+
+        // adcs r0, r1 (C=1)
+        execute16(&mut system, 0x4148);
+        assert_eq!(system.cpu.r[0], 2);
+        assert_eq!(system.cpu.apsr, 0);
+
+        // sbcs r0, r1 (C=0 means borrow)
+        execute16(&mut system, 0x4188);
+        assert_eq!(system.cpu.r[0], 0);
+        assert_eq!(system.cpu.apsr, 0b0110 << 28);
+
+        // rsbs r2, r1, #0
+        execute16(&mut system, 0x424a);
+        assert_eq!(system.cpu.r[2], 0xffffffff);
+        assert_eq!(system.cpu.apsr, 0b1000 << 28);
+
+        // cmp r1, r2
+        execute16(&mut system, 0x4291);
+        assert_eq!(system.cpu.apsr, 0);
+
+        // cmn r1, r2
+        execute16(&mut system, 0x42d1);
+        assert_eq!(system.cpu.apsr, 0b0110 << 28);
+        assert_eq!(system.cpu.r[1], 1);
+        assert_eq!(system.cpu.r[2], 0xffffffff);
+
+        // adds r3, r1, r2 overflows into the sign bit
+        system.cpu.r[1] = 0x7fffffff;
+        system.cpu.r[2] = 1;
+        execute16(&mut system, 0x188b);
+        assert_eq!(system.cpu.r[3], 0x80000000);
+        assert_eq!(system.cpu.apsr, 0b1001 << 28);
+    }
+
+    #[test]
+    fn test_load_store_immediate_offset() {
+        let mut system: M0System = test_system();
+        system.cpu.r[0] = 0x12345678;
+        system.cpu.r[1] = 0x800;
+
+        // str r0, [r1, #4]
+        assert_eq!(execute16(&mut system, 0x6048), 2);
+        assert_eq!(system.system_map.read32(0x804).unwrap(), 0x12345678);
+        // strh r0, [r1, #2]
+        execute16(&mut system, 0x8048);
+        assert_eq!(system.system_map.read16(0x802).unwrap(), 0x5678);
+        // strb r0, [r1, #1]
+        execute16(&mut system, 0x7048);
+        assert_eq!(system.system_map.read8(0x801).unwrap(), 0x78);
+
+        // ldr r2, [r1, #4]
+        execute16(&mut system, 0x684a);
+        assert_eq!(system.cpu.r[2], 0x12345678);
+        // ldrh r3, [r1, #6]
+        system.system_map.write32(0x804, 0x89abcdef).unwrap();
+        execute16(&mut system, 0x88cb);
+        assert_eq!(system.cpu.r[3], 0x89ab);
+        // ldrb r4, [r1, #7]
+        execute16(&mut system, 0x79cc);
+        assert_eq!(system.cpu.r[4], 0x89);
+        assert_eq!(system.cpu.pc, 0x10c);
+
+        // ldr r2, [r1, #124] is the largest word offset
+        system.system_map.write32(0x87c, 0xcafebabe).unwrap();
+        execute16(&mut system, 0x6fca);
+        assert_eq!(system.cpu.r[2], 0xcafebabe);
+    }
+
+    #[test]
+    fn test_load_store_immediate_offset_error() {
+        let mut system: M0System = test_system();
+        system.fault_action = FaultAction::Stop;
+        system.cpu.r[1] = 0x20000000;
+
+        // str r0, [r1, #0] to unmapped address
+        assert_eq!(execute16(&mut system, 0x6008), 0);
+        assert_eq!(system.cpu.pc, 0x100);
+
+        // ldrh r0, [r1, #0] from unaligned address
+        system.cpu.r[1] = 0x801;
+        assert_eq!(execute16(&mut system, 0x8808), 0);
+        assert_eq!(system.cpu.pc, 0x100);
+    }
+
+    #[test]
+    fn test_load_store_register_offset() {
+        let mut system: M0System = test_system();
+        system.cpu.r[0] = 0x8badf00d;
+        system.cpu.r[1] = 0x800;
+        system.cpu.r[2] = 0x10;
+
+        // str r0, [r1, r2]
+        assert_eq!(execute16(&mut system, 0x5088), 2);
+        assert_eq!(system.system_map.read32(0x810).unwrap(), 0x8badf00d);
+        // strh r0, [r1, r2]
+        system.cpu.r[2] = 0x20;
+        execute16(&mut system, 0x5288);
+        assert_eq!(system.system_map.read32(0x820).unwrap(), 0x0000f00d);
+        // strb r0, [r1, r2]
+        system.cpu.r[2] = 0x30;
+        execute16(&mut system, 0x5488);
+        assert_eq!(system.system_map.read32(0x830).unwrap(), 0x0000000d);
+
+        // ldr r3, [r1, r2]
+        system.cpu.r[2] = 0x10;
+        execute16(&mut system, 0x588b);
+        assert_eq!(system.cpu.r[3], 0x8badf00d);
+        // ldrh r3, [r1, r2]
+        execute16(&mut system, 0x5a8b);
+        assert_eq!(system.cpu.r[3], 0x0000f00d);
+        // ldrsh r3, [r1, r2]
+        execute16(&mut system, 0x5e8b);
+        assert_eq!(system.cpu.r[3], 0xfffff00d);
+        // ldrb r3, [r1, r2]
+        system.cpu.r[2] = 0x13;
+        execute16(&mut system, 0x5c8b);
+        assert_eq!(system.cpu.r[3], 0x0000008b);
+        // ldrsb r3, [r1, r2]
+        execute16(&mut system, 0x568b);
+        assert_eq!(system.cpu.r[3], 0xffffff8b);
+        // ldrsb r3, [r1, r2] with a positive byte
+        system.cpu.r[2] = 0x10;
+        execute16(&mut system, 0x568b);
+        assert_eq!(system.cpu.r[3], 0x0000000d);
+        // ldrsh r3, [r1, r2] with a positive halfword
+        system.cpu.r[2] = 0x0;
+        system.system_map.write16(0x800, 0x7fff).unwrap();
+        execute16(&mut system, 0x5e8b);
+        assert_eq!(system.cpu.r[3], 0x00007fff);
+        assert_eq!(system.cpu.pc, 0x114);
+    }
+
+    #[test]
+    fn test_load_store_stack() {
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.sp[1] = 0x900;
+        system.cpu.r[3] = 0xdeadbeef;
+
+        // str r3, [sp, #8]
+        assert_eq!(execute16(&mut system, 0x9302), 2);
+        assert_eq!(system.system_map.read32(0x808).unwrap(), 0xdeadbeef);
+        // ldr r4, [sp, #8]
+        execute16(&mut system, 0x9c02);
+        assert_eq!(system.cpu.r[4], 0xdeadbeef);
+
+        // the active stack pointer is used
+        system.cpu.ctrl_spsel = 1;
+        // str r3, [sp, #1020]
+        execute16(&mut system, 0x93ff);
+        assert_eq!(system.system_map.read32(0xcfc).unwrap(), 0xdeadbeef);
+        // ldr r5, [sp, #1020]
+        execute16(&mut system, 0x9dff);
+        assert_eq!(system.cpu.r[5], 0xdeadbeef);
+        assert_eq!(system.cpu.sp, [0x800, 0x900]);
+        assert_eq!(system.cpu.pc, 0x108);
+    }
+
+    #[test]
+    fn test_pc_relative() {
+        let mut system: M0System = test_system();
+        system.system_map.write32(0x108, 0x11111111).unwrap();
+        system.system_map.write32(0x10c, 0x22222222).unwrap();
+
+        // adr r0, #4 (at word aligned address)
+        execute16(&mut system, 0xa001);
+        assert_eq!(system.cpu.r[0], 0x108);
+        // adr r1, #4 (at halfword aligned address)
+        execute16(&mut system, 0xa101);
+        assert_eq!(system.cpu.r[1], 0x108);
+        // ldr r2, [pc, #4]
+        execute16(&mut system, 0x4a01);
+        assert_eq!(system.cpu.r[2], 0x22222222);
+        // ldr r3, [pc, #0]
+        execute16(&mut system, 0x4b00);
+        assert_eq!(system.cpu.r[3], 0x11111111);
+        assert_eq!(system.cpu.pc, 0x108);
+
+        // the PC base wraps at the top of the address space
+        system.system_map.write32(0x4, 0x33333333).unwrap();
+        system.cpu.pc = 0xfffffffc;
+        // adr r0, #4
+        add_to_pc(0xa001, &mut system);
+        assert_eq!(system.cpu.r[0], 0x4);
+        system.cpu.pc = 0xfffffffc;
+        // ldr r4, [pc, #4]
+        load_from_literal_pool(0x4c01, &mut system);
+        assert_eq!(system.cpu.r[4], 0x33333333);
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x800;
+        for i in 0..8 {
+            system.cpu.r[i] = 0x10 + i as u32;
+        }
+        system.cpu.lr = 0x00000201;
+
+        // push {r0, r2, r7, lr}
+        assert_eq!(execute16(&mut system, 0xb585), 5);
+        assert_eq!(system.cpu.sp[0], 0x7f0);
+        assert_eq!(system.system_map.read32(0x7f0).unwrap(), 0x10);
+        assert_eq!(system.system_map.read32(0x7f4).unwrap(), 0x12);
+        assert_eq!(system.system_map.read32(0x7f8).unwrap(), 0x17);
+        assert_eq!(system.system_map.read32(0x7fc).unwrap(), 0x201);
+
+        // pop {r1, r3, r4}
+        assert_eq!(execute16(&mut system, 0xbc1a), 4);
+        assert_eq!(system.cpu.r[1], 0x10);
+        assert_eq!(system.cpu.r[3], 0x12);
+        assert_eq!(system.cpu.r[4], 0x17);
+        assert_eq!(system.cpu.sp[0], 0x7fc);
+        assert_eq!(system.cpu.pc, 0x104);
+
+        // pop {pc}
+        assert_eq!(execute16(&mut system, 0xbd00), 4);
+        assert_eq!(system.cpu.sp[0], 0x800);
+        assert_eq!(system.cpu.pc, 0x200);
+        assert_eq!(system.cpu.epsr & EPSR_T, EPSR_T);
+    }
+
+    #[test]
+    fn test_push_stack_wrap() {
+        let mut system: M0System = test_system();
+        system.fault_action = FaultAction::Stop;
+
+        // push {r0, lr} below address 0 is a bus error, not an overflow
+        system.cpu.sp[0] = 0;
+        assert_eq!(execute16(&mut system, 0xb501), 0);
+        assert_eq!(system.cpu.sp[0], 0);
+        assert_eq!(system.cpu.pc, 0x100);
+    }
+
+    #[test]
+    fn test_pop_pc_interworking() {
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x7fc;
+        system.system_map.write32(0x7fc, 0x00000200).unwrap();
+
+        // pop {pc} to an address with bit 0 cleared leaves thumb state
+        execute16(&mut system, 0xbd00);
+        assert_eq!(system.cpu.pc, 0x200);
+        assert_eq!(system.cpu.epsr & EPSR_T, 0);
+        assert_eq!(execute16(&mut system, 0xbf00), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.ipsr, EXC_HARDFAULT as u32);
+        assert_eq!(system.system_map.read32(0x7e0 + 0x18).unwrap(), 0x200);
+
+        // push {} and pop {} are unpredictable
+        system.cpu.epsr = EPSR_T;
+        assert_eq!(execute16(&mut system, 0xb400), 0);
+        assert_eq!(execute16(&mut system, 0xbc00), 0);
+    }
+
+    #[test]
+    fn test_load_store_multiple() {
+        let mut system: M0System = test_system();
+        system.fault_action = FaultAction::Stop;
+        system.cpu.r[0] = 0x400;
+        system.cpu.r[1] = 0x11111111;
+        system.cpu.r[2] = 0x22222222;
+        system.cpu.r[3] = 0x33333333;
+
+        // stmia r0!, {r1, r2, r3}
+        assert_eq!(execute16(&mut system, 0xc00e), 4);
+        assert_eq!(system.cpu.r[0], 0x40c);
+        assert_eq!(system.system_map.read32(0x400).unwrap(), 0x11111111);
+        assert_eq!(system.system_map.read32(0x404).unwrap(), 0x22222222);
+        assert_eq!(system.system_map.read32(0x408).unwrap(), 0x33333333);
+
+        // ldmia r4!, {r5, r6, r7}
+        system.cpu.r[4] = 0x400;
+        assert_eq!(execute16(&mut system, 0xcce0), 4);
+        assert_eq!(system.cpu.r[4], 0x40c);
+        assert_eq!(system.cpu.r[5], 0x11111111);
+        assert_eq!(system.cpu.r[6], 0x22222222);
+        assert_eq!(system.cpu.r[7], 0x33333333);
+
+        // ldmia r4, {r3, r4}: no writeback when Rn is in the list
+        system.cpu.r[4] = 0x404;
+        assert_eq!(execute16(&mut system, 0xcc18), 3);
+        assert_eq!(system.cpu.r[3], 0x22222222);
+        assert_eq!(system.cpu.r[4], 0x33333333);
+
+        // stmia r0!, {r0, r1}: Rn as the lowest register stores the original value
+        system.cpu.r[0] = 0x500;
+        assert_eq!(execute16(&mut system, 0xc003), 3);
+        assert_eq!(system.cpu.r[0], 0x508);
+        assert_eq!(system.system_map.read32(0x500).unwrap(), 0x500);
+        assert_eq!(system.cpu.pc, 0x108);
+
+        // stmia r1!, {r0, r1} is unpredictable, and so is an empty list
+        system.cpu.r[1] = 0x600;
+        assert_eq!(execute16(&mut system, 0xc103), 0);
+        assert_eq!(execute16(&mut system, 0xc800), 0);
+
+        // unaligned base address
+        system.cpu.r[2] = 0x602;
+        assert_eq!(execute16(&mut system, 0xca01), 0);
+        assert_eq!(system.cpu.pc, 0x108);
+    }
+
+    #[test]
+    fn test_branch() {
+        let mut system: M0System = test_system();
+
+        // b.n 0x108 (PC + 4 base)
+        assert_eq!(execute16(&mut system, 0xe002), 3);
+        assert_eq!(system.cpu.pc, 0x108);
+        // b.n . loops on itself
+        assert_eq!(execute16(&mut system, 0xe7fe), 3);
+        assert_eq!(system.cpu.pc, 0x108);
+
+        // cmp r0, #1 ; beq backward taken
+        system.cpu.r[0] = 1;
+        execute16(&mut system, 0x2801);
+        assert_eq!(system.cpu.pc, 0x10a);
+        assert_eq!(execute16(&mut system, 0xd0fb), 3);
+        assert_eq!(system.cpu.pc, 0x104);
+
+        // bne not taken
+        assert_eq!(execute16(&mut system, 0xd1fb), 1);
+        assert_eq!(system.cpu.pc, 0x106);
+
+        // cmp r0, #2 ; bgt not taken, ble forward taken
+        execute16(&mut system, 0x2802);
+        assert_eq!(execute16(&mut system, 0xdc7f), 1);
+        assert_eq!(system.cpu.pc, 0x10a);
+        assert_eq!(execute16(&mut system, 0xdd7f), 3);
+        assert_eq!(system.cpu.pc, 0x10a + 4 + 0xfe);
+    }
+
+    #[test]
+    fn test_special_data_processing() {
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.apsr = 0;
+
+        // mov r8, r0 / mov r1, r8
+        system.cpu.r[0] = 0x12345678;
+        assert_eq!(execute16(&mut system, 0x4680), 1);
+        assert_eq!(system.cpu.r[8], 0x12345678);
+        assert_eq!(execute16(&mut system, 0x4641), 1);
+        assert_eq!(system.cpu.r[1], 0x12345678);
+
+        // add sp, r2 (SP stays word aligned) / add r2, sp
+        system.cpu.r[2] = 0x11;
+        assert_eq!(execute16(&mut system, 0x4495), 1);
+        assert_eq!(system.cpu.sp[0], 0x810);
+        assert_eq!(execute16(&mut system, 0x446a), 1);
+        assert_eq!(system.cpu.r[2], 0x821);
+        assert_eq!(system.cpu.apsr, 0);
+
+        // add r3, pc reads PC as the instruction address + 4
+        system.cpu.r[3] = 0;
+        let pc: u32 = system.cpu.pc;
+        execute16(&mut system, 0x447b);
+        assert_eq!(system.cpu.r[3], pc + 4);
+
+        // cmp r8, r1 sets flags
+        assert_eq!(execute16(&mut system, 0x4588), 1);
+        assert_eq!(system.cpu.apsr >> 28, 0b0110);
+
+        // mov pc, lr branches
+        system.cpu.lr = 0x201;
+        assert_eq!(execute16(&mut system, 0x46f7), 3);
+        assert_eq!(system.cpu.pc, 0x200);
+
+        // add pc, r4 branches
+        system.cpu.r[4] = 0x10;
+        assert_eq!(execute16(&mut system, 0x44a7), 3);
+        assert_eq!(system.cpu.pc, 0x214);
+
+        // cmp r0, r1 with two low registers, add pc, pc and cmp pc, r8 are unpredictable
+        assert_eq!(execute16(&mut system, 0x4508), 0);
+        assert_eq!(execute16(&mut system, 0x44ff), 0);
+        assert_eq!(execute16(&mut system, 0x45c7), 0);
+        assert_eq!(system.cpu.pc, 0x214);
+    }
+
+    #[test]
+    fn test_bx_blx() {
+        let mut system: M0System = test_system();
+        system.cpu.lr = 0x0f01;
+
+        // bx r1 does not touch LR
+        system.cpu.r[1] = 0x301;
+        assert_eq!(execute16(&mut system, 0x4708), 3);
+        assert_eq!(system.cpu.pc, 0x300);
+        assert_eq!(system.cpu.lr, 0x0f01);
+
+        // blx r2 links the next instruction with the thumb bit
+        system.cpu.r[2] = 0x401;
+        assert_eq!(execute16(&mut system, 0x4790), 3);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.lr, 0x303);
+        assert_eq!(system.cpu.epsr & EPSR_T, EPSR_T);
+
+        // bx pc and blx pc are unpredictable
+        assert_eq!(execute16(&mut system, 0x4778), 0);
+        assert_eq!(execute16(&mut system, 0x47f8), 0);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.lr, 0x303);
+    }
+
+    #[test]
+    fn test_adjust_stack_pointer() {
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.apsr = 0xf0000000;
+
+        // sub sp, #8 / add sp, #4 / add r0, sp, #16 leave the flags alone
+        execute16(&mut system, 0xb082);
+        assert_eq!(system.cpu.sp[0], 0x7f8);
+        execute16(&mut system, 0xb001);
+        assert_eq!(system.cpu.sp[0], 0x7fc);
+        execute16(&mut system, 0xa804);
+        assert_eq!(system.cpu.r[0], 0x80c);
+        assert_eq!(system.cpu.apsr, 0xf0000000);
+    }
+
+    #[test]
+    fn test_extend_reverse() {
+        let mut system: M0System = test_system();
+        system.cpu.r[0] = 0x12348786;
+
+        // sxth r1, r0 / sxtb r2, r0 / uxth r3, r0 / uxtb r4, r0
+        assert_eq!(execute16(&mut system, 0xb201), 1);
+        assert_eq!(system.cpu.r[1], 0xffff8786);
+        assert_eq!(execute16(&mut system, 0xb242), 1);
+        assert_eq!(system.cpu.r[2], 0xffffff86);
+        assert_eq!(execute16(&mut system, 0xb283), 1);
+        assert_eq!(system.cpu.r[3], 0x00008786);
+        assert_eq!(execute16(&mut system, 0xb2c4), 1);
+        assert_eq!(system.cpu.r[4], 0x00000086);
+
+        // rev r1, r0 / rev16 r2, r0 / revsh r3, r0
+        assert_eq!(execute16(&mut system, 0xba01), 1);
+        assert_eq!(system.cpu.r[1], 0x86873412);
+        assert_eq!(execute16(&mut system, 0xba42), 1);
+        assert_eq!(system.cpu.r[2], 0x34128687);
+        assert_eq!(execute16(&mut system, 0xbac3), 1);
+        assert_eq!(system.cpu.r[3], 0xffff8687);
+        system.cpu.r[0] = 0x00001234;
+        execute16(&mut system, 0xbac3);
+        assert_eq!(system.cpu.r[3], 0x00003412);
+        assert_eq!(system.cpu.pc, 0x110);
+    }
+
+    #[test]
+    fn test_cps() {
+        let mut system: M0System = test_system();
+
+        // cpsid i / cpsie i
+        assert_eq!(execute16(&mut system, 0xb672), 1);
+        assert_eq!(system.cpu.primask_pm, 1);
+        assert_eq!(execute16(&mut system, 0xb662), 1);
+        assert_eq!(system.cpu.primask_pm, 0);
+
+        // no effect in unprivileged thread mode
+        system.cpu.ctrl_npriv = 1;
+        execute16(&mut system, 0xb672);
+        assert_eq!(system.cpu.primask_pm, 0);
+
+        // handler mode is always privileged
+        system.cpu.ipsr = 11;
+        execute16(&mut system, 0xb672);
+        assert_eq!(system.cpu.primask_pm, 1);
+        assert_eq!(system.cpu.pc, 0x108);
+    }
+
+    #[test]
+    fn test_mrs_msr() {
+        let mut system: M0System = test_system();
+        system.cpu.apsr = 0xa0000000;
+        system.cpu.sp[0] = 0x800;
+        system.cpu.r[1] = 0x50000000;
+
+        // mrs r0, apsr / msr apsr, r1
+        assert_eq!(execute32(&mut system, 0xf3ef8000), 3);
+        assert_eq!(system.cpu.r[0], 0xa0000000);
+        assert_eq!(system.cpu.pc, 0x104);
+        // the bits below the flags are kept as on unstacking
+        system.cpu.apsr |= 0x08000000;
+        execute32(&mut system, 0xf3818800);
+        assert_eq!(system.cpu.apsr, 0x58000000);
+
+        // mrs r2, xpsr / mrs r3, ipsr in handler mode
+        system.cpu.ipsr = 3;
+        execute32(&mut system, 0xf3ef8203);
+        assert_eq!(system.cpu.r[2], 0x50000003);
+        execute32(&mut system, 0xf3ef8305);
+        assert_eq!(system.cpu.r[3], 0x00000003);
+        system.cpu.ipsr = 0;
+
+        // msr psp, r4 / mrs r5, psp / mrs r6, msp
+        system.cpu.r[4] = 0x403;
+        execute32(&mut system, 0xf3848809);
+        assert_eq!(system.cpu.sp[1], 0x400);
+        execute32(&mut system, 0xf3ef8509);
+        assert_eq!(system.cpu.r[5], 0x400);
+        execute32(&mut system, 0xf3ef8608);
+        assert_eq!(system.cpu.r[6], 0x800);
+
+        // msr primask, r7 / mrs r0, primask
+        system.cpu.r[7] = 1;
+        execute32(&mut system, 0xf3878810);
+        assert_eq!(system.cpu.primask_pm, 1);
+        execute32(&mut system, 0xf3ef8010);
+        assert_eq!(system.cpu.r[0], 1);
+
+        // msr control, r1 switches to PSP and unprivileged thread mode
+        system.cpu.r[1] = 0b11;
+        execute32(&mut system, 0xf3818814);
+        assert_eq!(system.cpu.ctrl_spsel, 1);
+        assert_eq!(system.cpu.ctrl_npriv, 1);
+        execute32(&mut system, 0xf3ef8014);
+        assert_eq!(system.cpu.r[0], 0b11);
+
+        // unprivileged writes to msp, primask, nPRIV and SPSEL are ignored
+        system.cpu.r[2] = 0;
+        execute32(&mut system, 0xf3828808);
+        assert_eq!(system.cpu.sp[0], 0x800);
+        execute32(&mut system, 0xf3828810);
+        assert_eq!(system.cpu.primask_pm, 1);
+        execute32(&mut system, 0xf3828814);
+        assert_eq!(system.cpu.ctrl_npriv, 1);
+        assert_eq!(system.cpu.ctrl_spsel, 1);
+        execute32(&mut system, 0xf3ef8308);
+        assert_eq!(system.cpu.r[3], 0);
+
+        // mrs sp, apsr is unpredictable
+        assert_eq!(execute32(&mut system, 0xf3ef8d00), 0);
+    }
+
+    #[test]
+    fn test_barriers() {
+        let mut system: M0System = test_system();
+
+        // dsb sy / dmb sy / isb sy
+        assert_eq!(execute32(&mut system, 0xf3bf8f4f), 4);
+        assert_eq!(execute32(&mut system, 0xf3bf8f5f), 4);
+        assert_eq!(execute32(&mut system, 0xf3bf8f6f), 4);
+        assert_eq!(system.cpu.pc, 0x10c);
+    }
+
+    #[test]
+    fn test_branch_32() {
+        let mut system: M0System = test_system();
+
+        // bl forward to 0x100 + 4 + 0x1234
+        assert_eq!(execute32(&mut system, 0xf001f91a), 4);
+        assert_eq!(system.cpu.pc, 0x1338);
+        assert_eq!(system.cpu.lr, 0x105);
+
+        // bl backward to 0x200
+        system.cpu.pc = 0x400;
+        execute32(&mut system, 0xf7fffefe);
+        assert_eq!(system.cpu.pc, 0x200);
+        assert_eq!(system.cpu.lr, 0x405);
+
+        // bl with the largest negative offset (-16MB) exercises I1/I2
+        system.cpu.pc = 0x200;
+        execute32(&mut system, 0xf400d000);
+        assert_eq!(system.cpu.pc, 0x204u32.wrapping_sub(0x01000000));
+        assert_eq!(system.cpu.lr, 0x205);
+
+        // bl with J1 = J2 = 0 and S = 0 sets I1 = I2 = 1 (+0xc00000)
+        system.cpu.pc = 0x200;
+        execute32(&mut system, 0xf000d000);
+        assert_eq!(system.cpu.pc, 0x204 + 0x00c00000);
+
+        // b.w backward and forward
+        system.cpu.pc = 0x400;
+        assert_eq!(execute32(&mut system, 0xf7ffbefe), 3);
+        assert_eq!(system.cpu.pc, 0x200);
+        execute32(&mut system, 0xf000b87e);
+        assert_eq!(system.cpu.pc, 0x300);
+        assert_eq!(system.cpu.lr, 0x205);
+    }
+
+    #[test]
+    fn test_exception_entry_return() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_PENDSV as u32, 0x301)
+            .unwrap();
+        system.cpu.sp[0] = 0x804;
+        system.cpu.r[0] = 0x10;
+        system.cpu.r[3] = 0x13;
+        system.cpu.r[12] = 0x1c;
+        system.cpu.lr = 0x0f01;
+        system.cpu.apsr = 0x80000000;
+
+        // stacks r0-r3, r12, lr, pc, xpsr on an 8-byte aligned frame
+        set_exception_pending(&mut system, EXC_PENDSV);
+        assert_eq!(system.execute(), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.sp[0], 0x7e0);
+        assert_eq!(system.system_map.read32(0x7e0).unwrap(), 0x10);
+        assert_eq!(system.system_map.read32(0x7ec).unwrap(), 0x13);
+        assert_eq!(system.system_map.read32(0x7f0).unwrap(), 0x1c);
+        assert_eq!(system.system_map.read32(0x7f4).unwrap(), 0x0f01);
+        assert_eq!(system.system_map.read32(0x7f8).unwrap(), 0x100);
+        assert_eq!(
+            system.system_map.read32(0x7fc).unwrap(),
+            0x80000000 | EPSR_T | (0b1 << 9)
+        );
+        assert_eq!(system.cpu.lr, 0xfffffff9);
+        assert_eq!(system.cpu.pc, 0x300);
+        assert_eq!(system.cpu.ipsr, EXC_PENDSV as u32);
+        assert!(system.cpu.exception_active[EXC_PENDSV]);
+        assert!(!is_exception_pending(&system, EXC_PENDSV));
+
+        // bx lr unstacks and returns to thread mode
+        system.cpu.r[0] = 0;
+        system.cpu.apsr = 0;
+        assert_eq!(execute16(&mut system, 0x4770), EXCEPTION_RETURN_CYCLES);
+        assert_eq!(system.cpu.pc, 0x100);
+        assert_eq!(system.cpu.sp[0], 0x804);
+        assert_eq!(system.cpu.r[0], 0x10);
+        assert_eq!(system.cpu.lr, 0x0f01);
+        assert_eq!(system.cpu.apsr, 0x80000000);
+        assert_eq!(system.cpu.ipsr, 0);
+        assert!(!system.cpu.exception_active[EXC_PENDSV]);
+    }
+
+    #[test]
+    fn test_exception_process_stack() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_PENDSV as u32, 0x301)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.sp[1] = 0x600;
+        system.cpu.ctrl_spsel = 1;
+
+        // thread mode on PSP stacks to PSP and runs the handler on MSP
+        set_exception_pending(&mut system, EXC_PENDSV);
+        system.execute();
+        assert_eq!(system.cpu.sp[1], 0x5e0);
+        assert_eq!(system.cpu.sp[0], 0x800);
+        assert_eq!(system.cpu.ctrl_spsel, 0);
+        assert_eq!(system.cpu.lr, 0xfffffffd);
+
+        // push {lr} ... pop {pc} returns with EXC_RETURN
+        execute16(&mut system, 0xb500);
+        assert_eq!(system.cpu.sp[0], 0x7fc);
+        assert_eq!(execute16(&mut system, 0xbd00), EXCEPTION_RETURN_CYCLES + 1);
+        assert_eq!(system.cpu.sp[0], 0x800);
+        assert_eq!(system.cpu.sp[1], 0x600);
+        assert_eq!(system.cpu.ctrl_spsel, 1);
+        assert_eq!(system.cpu.pc, 0x100);
+    }
+
+    #[test]
+    fn test_exception_priority() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_NMI as u32, 0x201)
+            .unwrap();
+        system
+            .system_map
+            .write32(4 * EXC_PENDSV as u32, 0x301)
+            .unwrap();
+        system
+            .system_map
+            .write32(4 * EXC_SYSTICK as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        // PendSV: 0xc0, SysTick: 0x40
+        system.cpu.shpr3 = 0x40c00000;
+
+        // PRIMASK holds off configurable exceptions
+        system.cpu.primask_pm = 1;
+        set_exception_pending(&mut system, EXC_PENDSV);
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+        assert_eq!(system.cpu.pc, 0x102);
+        system.cpu.primask_pm = 0;
+        system.execute();
+        assert_eq!(system.cpu.pc, 0x300);
+
+        // SysTick preempts PendSV
+        set_exception_pending(&mut system, EXC_SYSTICK);
+        system.execute();
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.lr, 0xfffffff1);
+
+        // PendSV does not preempt SysTick, NMI preempts everything
+        set_exception_pending(&mut system, EXC_PENDSV);
+        system.cpu.primask_pm = 1;
+        set_exception_pending(&mut system, EXC_NMI);
+        system.execute();
+        assert_eq!(system.cpu.pc, 0x200);
+        assert_eq!(system.cpu.ipsr, EXC_NMI as u32);
+
+        // return from NMI to SysTick; PendSV still waits
+        execute16(&mut system, 0x4770);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, EXC_SYSTICK as u32);
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+        assert!(is_exception_pending(&system, EXC_PENDSV));
+    }
+
+    #[test]
+    fn test_exception_bad_return() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_PENDSV as u32, 0x301)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        set_exception_pending(&mut system, EXC_PENDSV);
+        system.execute();
+
+        // bx to an EXC_RETURN with a reserved value is a HardFault
+        system
+            .system_map
+            .write32(4 * EXC_HARDFAULT as u32, 0x401)
+            .unwrap();
+        system.cpu.r[0] = 0xfffffff5;
+        assert_eq!(execute16(&mut system, 0x4700), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, EXC_HARDFAULT as u32);
+        assert!(system.cpu.exception_active[EXC_PENDSV]);
+        assert_eq!(system.cpu.lr, 0xfffffff1);
+
+        // a stacked IPSR out of the exception numbers is a HardFault, nothing is unstacked
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_PENDSV as u32, 0x301)
+            .unwrap();
+        system
+            .system_map
+            .write32(4 * EXC_HARDFAULT as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        set_exception_pending(&mut system, EXC_PENDSV);
+        system.execute();
+        system.system_map.write32(0x7fc, EPSR_T | 50).unwrap();
+        system.cpu.r[0] = 0xfffffff1;
+        assert_eq!(execute16(&mut system, 0x4700), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, EXC_HARDFAULT as u32);
+        assert_eq!(system.cpu.sp[0], 0x7c0);
+        assert_eq!(system.system_map.read32(0x7c0).unwrap(), 0xfffffff1);
+        assert!(system.fault_chain[0].starts_with("invalid EXC_RETURN"));
+
+        // a return to handler mode on a thread mode frame stops before unstacking
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_PENDSV as u32, 0x301)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        set_exception_pending(&mut system, EXC_PENDSV);
+        system.execute();
+        system.cpu.r[0] = 0xfffffff1;
+        assert_eq!(execute16(&mut system, 0x4700), 0);
+        assert_eq!(system.cpu.r[0], 0xfffffff1);
+        assert_eq!(system.cpu.sp[0], 0x7e0);
+        assert_eq!(system.cpu.ipsr, EXC_PENDSV as u32);
+    }
+
+    #[test]
+    fn test_hard_fault() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_HARDFAULT as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+
+        // udf #0 stacks the address of the faulting instruction
+        assert_eq!(execute16(&mut system, 0xde00), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, EXC_HARDFAULT as u32);
+        assert_eq!(system.system_map.read32(0x7e0 + 0x18).unwrap(), 0x100);
+
+        // a fault in the HardFault handler locks up: ldr r0, [r1]
+        system.cpu.r[1] = 0x20000000;
+        assert_eq!(execute16(&mut system, 0x6808), 0);
+        assert_eq!(system.state, RunState::Lockup);
+
+        // instruction fetch from an unmapped address
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_HARDFAULT as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.pc = 0x2000;
+        assert_eq!(system.execute(), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.system_map.read32(0x7e0 + 0x18).unwrap(), 0x2000);
+
+        // literal pool load from an unmapped address: ldr r0, [pc, #1020]
+        system.cpu.pc = 0xc00;
+        system.cpu.ipsr = 0;
+        system.cpu.exception_active[EXC_HARDFAULT] = false;
+        assert_eq!(execute16(&mut system, 0x48ff), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+
+        // the simulation stops instead of taking HardFault
+        let mut system: M0System = test_system();
+        system.fault_action = FaultAction::Stop;
+        assert_eq!(execute16(&mut system, 0xde00), 0);
+        assert_eq!(system.cpu.pc, 0x100);
+        assert_eq!(system.cpu.ipsr, 0);
+        assert_eq!(system.state, RunState::Stopped);
+
+        // the stopped processor does not execute until reset
+        assert_eq!(execute16(&mut system, 0xbf00), 0);
+        assert_eq!(system.cpu.pc, 0x100);
+        system.system_map.write32(0, 0x800).unwrap();
+        system.system_map.write32(4, 0x101).unwrap();
+        system.reset();
+        assert_eq!(system.state, RunState::Running);
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+        assert_eq!(system.cpu.pc, 0x102);
+    }
+
+    #[test]
+    fn test_lockup() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_HARDFAULT as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.r[1] = 0x20000000;
+
+        // nop, udf #0 and ldr r0, [r1] in the HardFault handler
+        execute16(&mut system, 0xbf00);
+        execute16(&mut system, 0xde00);
+        assert_eq!(execute16(&mut system, 0x6808), 0);
+        assert_eq!(system.state, RunState::Lockup);
+        assert_eq!(system.cpu.pc, LOCKUP_ADDRESS);
+        assert_eq!(system.fault_chain.len(), 2);
+        assert_eq!(
+            system.fault_chain[0],
+            "undefined instruction at 00000102 in exception 0"
+        );
+        assert_eq!(
+            system.fault_chain[1],
+            "bus error at 00000400 in exception 3"
+        );
+        let trace: Vec<(u32, u16)> = system.trace.iter().copied().collect();
+        assert_eq!(trace, [(0x100, 0xbf00), (0x102, 0xde00), (0x400, 0x6808)]);
+
+        let report: String = system.lockup_report();
+        assert!(report.contains("pc:\t\tfffffffe"));
+        assert!(report.contains("pc:\t00000102"));
+        assert!(report.contains("00000400:\t6808"));
+
+        assert!(report.contains("stacked registers (msp 000007e0)"));
+
+        // the locked up processor does not execute until reset
+        assert_eq!(system.execute(), 0);
+        assert_eq!(system.cpu.pc, LOCKUP_ADDRESS);
+
+        // HardFault stacking to an unmapped stack also locks up
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x20000000;
+        assert_eq!(execute16(&mut system, 0xde00), 0);
+        assert_eq!(system.state, RunState::Lockup);
+        assert_eq!(
+            system.fault_chain[1],
+            "bus error on entry of exception 3 at 00000100 in exception 0"
+        );
+        assert!(system.lockup_report().contains("stacked registers: none"));
+
+        // the frame of a fault on PSP is reported, not the MSP of the handler
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_HARDFAULT as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.sp[1] = 0x900;
+        system.cpu.ctrl_spsel = 1;
+        system.cpu.r[1] = 0x20000000;
+
+        // udf #0, then push {r0} and ldr r0, [r1] in the HardFault handler
+        execute16(&mut system, 0xde00);
+        execute16(&mut system, 0xb401);
+        assert_eq!(execute16(&mut system, 0x6808), 0);
+        assert_eq!(system.state, RunState::Lockup);
+        assert_eq!(system.cpu.sp[0], 0x7fc);
+        let report: String = system.lockup_report();
+        assert!(report.contains("stacked registers (psp 000008e0)"));
+        assert!(report.contains("pc:\t00000100"));
+
+        // a pending PendSV stacked to an unmapped stack locks up without fetching
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0x20000000;
+        set_exception_pending(&mut system, EXC_PENDSV);
+        assert_eq!(execute16(&mut system, 0xbf00), 0);
+        assert_eq!(system.state, RunState::Lockup);
+        assert_eq!(system.cpu.pc, LOCKUP_ADDRESS);
+        assert_eq!(system.fault_chain.len(), 2);
+        assert_eq!(
+            system.fault_chain[1],
+            "bus error on entry of exception 3 at 00000100 in exception 0"
+        );
+        assert!(system.trace.is_empty());
+
+        // the failed entry stops before the next instruction is executed
+        let mut system: M0System = test_system();
+        system.fault_action = FaultAction::Stop;
+        system.cpu.sp[0] = 0x20000000;
+        set_exception_pending(&mut system, EXC_PENDSV);
+        assert_eq!(execute16(&mut system, 0xbf00), 0);
+        assert_eq!(system.state, RunState::Stopped);
+        assert_eq!(system.cpu.pc, 0x100);
+        assert_eq!(system.fault_chain.len(), 1);
+        assert!(system.trace.is_empty());
+    }
+
+    #[test]
+    fn test_nvic_interrupt() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * (EXC_IRQ0 + 3) as u32, 0x301)
+            .unwrap();
+        system
+            .system_map
+            .write32(4 * (EXC_IRQ0 + 7) as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+
+        // a pending IRQ is not taken until it is enabled
+        system.system_map.nvic.raise_irq(3);
+        system.system_map.nvic.raise_irq(7);
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+
+        // IRQ7 has the higher priority: str r1, [r0] to ISER and IPR
+        system.cpu.r[0] = 0xe000e100;
+        system.cpu.r[1] = (0b1 << 3) | (0b1 << 7);
+        execute16(&mut system, 0x6001);
+        assert_eq!(system.system_map.read32(0xe000e100).unwrap(), 0x88);
+        system.system_map.write32(0xe000e400, 0x80000000).unwrap();
+        system.system_map.write32(0xe000e404, 0x40000000).unwrap();
+        assert_eq!(system.execute(), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, (EXC_IRQ0 + 7) as u32);
+        assert_eq!(system.system_map.read32(0xe000e200).unwrap(), 0x08);
+
+        // IRQ3 waits for IRQ7 to return and is tail-chained
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+        assert_eq!(execute16(&mut system, 0x4770), EXCEPTION_TAIL_CHAIN_CYCLES);
+        assert_eq!(system.cpu.pc, 0x300);
+        assert_eq!(system.cpu.ipsr, (EXC_IRQ0 + 3) as u32);
+
+        // equal priorities are taken in exception number order
+        execute16(&mut system, 0x4770);
+        system.system_map.write32(0xe000e400, 0).unwrap();
+        system.system_map.write32(0xe000e404, 0).unwrap();
+        set_exception_pending(&mut system, EXC_IRQ0 + 7);
+        set_exception_pending(&mut system, EXC_IRQ0 + 3);
+        system.execute();
+        assert_eq!(system.cpu.pc, 0x300);
+
+        // ICPR clears the pending state
+        system.system_map.write32(0xe000e280, 0x80).unwrap();
+        assert!(!is_exception_pending(&system, EXC_IRQ0 + 7));
+    }
+
+    #[test]
+    fn test_systick_exception() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_SYSTICK as u32, 0x301)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+
+        // str r0, [r1, #4] / str r0, [r1, #8] / str r2, [r1]: RVR = 9, clear CVR, enable with TICKINT
+        system.cpu.r[0] = 9;
+        system.cpu.r[1] = 0xe000e010;
+        system.cpu.r[2] = 0b111;
+        execute16(&mut system, 0x6048);
+        execute16(&mut system, 0x6088);
+        // the cycles of the enabling store already count: reload and 9 -> 8
+        assert_eq!(execute16(&mut system, 0x600a), 2);
+        assert_eq!(system.system_map.read32(0xe000e018).unwrap(), 8);
+
+        for _ in 0..7 {
+            assert_eq!(execute16(&mut system, 0xbf00), 1);
+        }
+        assert_eq!(system.system_map.read32(0xe000e018).unwrap(), 1);
+        assert!(!is_exception_pending(&system, EXC_SYSTICK));
+        execute16(&mut system, 0xbf00);
+        assert!(is_exception_pending(&system, EXC_SYSTICK));
+        assert_eq!(system.cpu.pc, 0x116);
+
+        // ldr r3, [r1] reads and clears COUNTFLAG
+        system.execute();
+        assert_eq!(system.cpu.pc, 0x300);
+        execute16(&mut system, 0x680b);
+        assert_eq!(system.cpu.r[3], 0x00010007);
+        execute16(&mut system, 0x680b);
+        assert_eq!(system.cpu.r[3], 0x00000007);
+    }
+
+    #[test]
+    fn test_scb_registers() {
+        let mut system: M0System = test_system();
+
+        assert_eq!(system.read32(0xe000ed00).unwrap(), 0x410cc200);
+        assert_eq!(system.read32(0xe000ed14).unwrap(), 0x00000208);
+        system.write32(0xe000ed14, 0).unwrap();
+        assert_eq!(system.read32(0xe000ed14).unwrap(), 0x00000208);
+
+        // only the implemented priority bits are writable
+        system.write32(0xe000ed1c, 0xffffffff).unwrap();
+        system.write32(0xe000ed20, 0xffffffff).unwrap();
+        assert_eq!(system.cpu.shpr2, 0xc0000000);
+        assert_eq!(system.cpu.shpr3, 0xc0c00000);
+        system.write32(0xe000ed08, 0x00000483).unwrap();
+        assert_eq!(system.cpu.vtor, 0x00000480);
+        system.write32(0xe000ed10, 0xffffffff).unwrap();
+        assert_eq!(system.read32(0xe000ed10).unwrap(), 0b10110);
+
+        // word access only
+        assert!(system.read8(0xe000ed00).is_err());
+        assert!(system.write16(0xe000ed04, 0).is_err());
+        assert!(system.read32(0xe000ed02).is_err());
+    }
+
+    #[test]
+    fn test_scb_icsr() {
+        let mut system: M0System = test_system();
+        system.write32(4 * EXC_PENDSV as u32, 0x301).unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.cpu.primask_pm = 1;
+
+        // PENDSVSET / PENDSTSET / PENDSTCLR
+        system
+            .write32(0xe000ed04, ICSR_PENDSVSET | ICSR_PENDSTSET)
+            .unwrap();
+        assert_eq!(
+            system.read32(0xe000ed04).unwrap(),
+            ICSR_PENDSVSET | ICSR_PENDSTSET | ((EXC_PENDSV as u32) << 12)
+        );
+        system.write32(0xe000ed04, ICSR_PENDSTCLR).unwrap();
+        assert!(!is_exception_pending(&system, EXC_SYSTICK));
+
+        // VECTACTIVE in the handler, the pending bit is cleared
+        system.cpu.primask_pm = 0;
+        system.execute();
+        assert_eq!(system.read32(0xe000ed04).unwrap(), EXC_PENDSV as u32);
+
+        // PENDSVCLR and ISRPENDING
+        system.write32(0xe000ed04, ICSR_PENDSVSET).unwrap();
+        system.write32(0xe000ed04, ICSR_PENDSVCLR).unwrap();
+        assert!(!is_exception_pending(&system, EXC_PENDSV));
+        system.system_map.nvic.enable = 0b1;
+        system.system_map.nvic.raise_irq(0);
+        assert_eq!(
+            system.read32(0xe000ed04).unwrap(),
+            ICSR_ISRPENDING | ((EXC_IRQ0 as u32) << 12) | EXC_PENDSV as u32
+        );
+    }
+
+    #[test]
+    fn test_scb_aircr_reset() {
+        let mut system: M0System = test_system();
+        system.write32(0, 0x800).unwrap();
+        system.write32(4, 0x201).unwrap();
+        system.cpu.r[0] = 0xe000ed0c;
+
+        // writes without VECTKEY are ignored
+        system.cpu.r[1] = AIRCR_SYSRESETREQ;
+        execute16(&mut system, 0x6001);
+        assert_eq!(system.cpu.pc, 0x102);
+        assert_eq!(system.read32(0xe000ed0c).unwrap(), 0xfa050000);
+
+        // SYSRESETREQ resets the system after the store completes
+        system.system_map.nvic.enable = 0b1;
+        system.cpu.r[1] = (AIRCR_VECTKEY << 16) | AIRCR_SYSRESETREQ;
+        execute16(&mut system, 0x6001);
+        assert_eq!(system.cpu.pc, 0x200);
+        assert_eq!(system.cpu.sp[0], 0x800);
+        assert_eq!(system.cpu.r[0], 0);
+        assert_eq!(system.system_map.nvic.enable, 0);
+        assert_eq!(system.read32(0xe000ed0c).unwrap(), 0xfa050000);
+    }
+
+    #[test]
+    fn test_scb_stack_relative() {
+        let mut system: M0System = test_system();
+        system.cpu.sp[0] = 0xe000ed00;
+
+        // str r0, [sp, #8] and ldr r1, [sp, #8] access VTOR
+        system.cpu.r[0] = 0x400;
+        assert_eq!(execute16(&mut system, 0x9002), 2);
+        assert_eq!(system.cpu.vtor, 0x400);
+        assert_eq!(execute16(&mut system, 0x9902), 2);
+        assert_eq!(system.cpu.r[1], 0x400);
+    }
+
+    #[test]
+    fn test_svc_pendsv() {
+        let mut system: M0System = test_system();
+        system.write32(4 * EXC_SVCALL as u32, 0x301).unwrap();
+        system.write32(4 * EXC_PENDSV as u32, 0x401).unwrap();
+        system.write32(0xe000ed1c, 0x40000000).unwrap();
+        system.write32(0xe000ed20, 0x00c00000).unwrap();
+        system.cpu.sp[0] = 0x800;
+
+        // svc #42 pends SVCall, taken with the next instruction stacked
+        assert_eq!(execute16(&mut system, 0xdf2a), 1);
+        assert_eq!(system.cpu.pc, 0x102);
+        assert!(is_exception_pending(&system, EXC_SVCALL));
+        assert_eq!(system.execute(), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x300);
+        assert_eq!(system.cpu.ipsr, EXC_SVCALL as u32);
+        let stacked_pc: u32 = system.read32(system.cpu.sp[0] + 0x18).unwrap();
+        assert_eq!(stacked_pc, 0x102);
+        assert_eq!(system.read16(stacked_pc - 2).unwrap() & 0xff, 42);
+
+        // PendSV set through ICSR waits for the SVCall handler: str r1, [r0]
+        system.cpu.r[0] = 0xe000ed04;
+        system.cpu.r[1] = ICSR_PENDSVSET;
+        assert_eq!(execute16(&mut system, 0x6001), 2);
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+        assert_eq!(system.cpu.ipsr, EXC_SVCALL as u32);
+
+        // bx lr tail-chains into PendSV
+        assert_eq!(execute16(&mut system, 0x4770), EXCEPTION_TAIL_CHAIN_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, EXC_PENDSV as u32);
+
+        // svc in a handler of a higher priority escalates to HardFault
+        system.write32(4 * EXC_HARDFAULT as u32, 0x501).unwrap();
+        system.write32(0xe000ed20, 0x00000000).unwrap();
+        assert_eq!(execute16(&mut system, 0xdf00), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x500);
+        assert_eq!(system.cpu.ipsr, EXC_HARDFAULT as u32);
+        assert!(!is_exception_pending(&system, EXC_SVCALL));
+    }
+
+    #[test]
+    fn test_tail_chain_late_arrival() {
+        let mut system: M0System = test_system();
+        system
+            .system_map
+            .write32(4 * EXC_SYSTICK as u32, 0x301)
+            .unwrap();
+        system
+            .system_map
+            .write32(4 * EXC_IRQ0 as u32, 0x401)
+            .unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.system_map.nvic.enable = 0b1;
+        system.system_map.nvic.priority[0] = 0x80;
+
+        // SysTick fires while IRQ0 is being stacked and arrives late
+        system.system_map.systick.rvr = 100;
+        system.system_map.systick.cvr = 5;
+        system.system_map.systick.csr = CSR_ENABLE | CSR_TICKINT;
+        system.system_map.nvic.raise_irq(0);
+        assert_eq!(system.execute(), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cycle_count, EXCEPTION_ENTRY_CYCLES as u64);
+        assert_eq!(system.cpu.pc, 0x300);
+        assert_eq!(system.cpu.ipsr, EXC_SYSTICK as u32);
+        assert_eq!(system.cpu.sp[0], 0x7e0);
+        assert!(is_exception_pending(&system, EXC_IRQ0));
+
+        // IRQ0 is tail-chained on the same frame without unstacking
+        assert_eq!(execute16(&mut system, 0x4770), EXCEPTION_TAIL_CHAIN_CYCLES);
+        assert_eq!(system.cpu.pc, 0x400);
+        assert_eq!(system.cpu.ipsr, EXC_IRQ0 as u32);
+        assert_eq!(system.cpu.sp[0], 0x7e0);
+        assert_eq!(system.cpu.lr, 0xfffffff9);
+        assert!(!system.cpu.exception_active[EXC_SYSTICK]);
+        assert!(system.cpu.exception_active[EXC_IRQ0]);
+
+        // and returns to the preempted thread
+        assert_eq!(execute16(&mut system, 0x4770), EXCEPTION_RETURN_CYCLES);
+        assert_eq!(system.cpu.pc, 0x100);
+        assert_eq!(system.cpu.sp[0], 0x800);
+        assert_eq!(system.cpu.ipsr, 0);
+        assert_eq!(
+            system.cycle_count,
+            (EXCEPTION_ENTRY_CYCLES + EXCEPTION_TAIL_CHAIN_CYCLES + EXCEPTION_RETURN_CYCLES) as u64
+        );
+    }
+
+    #[test]
+    fn test_interrupt_injection() {
+        let mut system: M0System = test_system();
+        system.write32(4 * EXC_NMI as u32, 0x501).unwrap();
+        system.write32(4 * (EXC_IRQ0 + 5) as u32, 0x301).unwrap();
+        system.cpu.sp[0] = 0x800;
+        system.system_map.nvic.enable = 0b1 << 5;
+        system.inject(Injection::irq(5, Trigger::Cycle(3)));
+        system.inject(Injection::nmi(Trigger::Pc(0x108)));
+
+        // IRQ5 is raised by the third cycle and taken after it
+        for _ in 0..3 {
+            assert_eq!(execute16(&mut system, 0xbf00), 1);
+        }
+        assert!(is_exception_pending(&system, EXC_IRQ0 + 5));
+        assert_eq!(system.execute(), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.ipsr, (EXC_IRQ0 + 5) as u32);
+        execute16(&mut system, 0x4770);
+        assert_eq!(system.cpu.pc, 0x106);
+
+        // NMI is raised before the instruction at 0x108 is executed
+        assert_eq!(execute16(&mut system, 0xbf00), 1);
+        assert_eq!(execute16(&mut system, 0xbf00), EXCEPTION_ENTRY_CYCLES);
+        assert_eq!(system.cpu.pc, 0x500);
+        assert_eq!(system.cpu.ipsr, EXC_NMI as u32);
+        assert_eq!(system.read32(system.cpu.sp[0] + 0x18).unwrap(), 0x108);
+        assert!(system.injections.is_empty());
+    }
+
+    #[test]
+    fn test_hints_and_undefined() {
+        let mut system: M0System = test_system();
+
+        // yield / wfe / wfi / sev execute as NOPs
+        assert_eq!(execute16(&mut system, 0xbf10), 1);
+        assert_eq!(execute16(&mut system, 0xbf20), 2);
+        assert_eq!(execute16(&mut system, 0xbf30), 2);
+        assert_eq!(execute16(&mut system, 0xbf40), 1);
+        assert_eq!(system.cpu.pc, 0x108);
+
+        // cbz, cbnz, it and bkpt fault without a debugger
+        for (bytecode, reason) in [
+            (0xb100, "undefined instruction"),
+            (0xb900, "undefined instruction"),
+            (0xbf08, "undefined instruction"),
+            (0xbe00, "breakpoint"),
+        ] {
+            let mut system: M0System = test_system();
+            system.fault_action = FaultAction::Stop;
+            assert_eq!(execute16(&mut system, bytecode), 0);
+            assert_eq!(system.cpu.pc, 0x100);
+            assert!(system.fault_chain[0].starts_with(reason));
+        }
+
+        // ldrh.w r0, [r1] is not a part of ARMv6-M
+        let mut system: M0System = test_system();
+        system.fault_action = FaultAction::Stop;
+        assert_eq!(execute32(&mut system, 0xf8b10000), 0);
+        assert!(system.fault_chain[0].starts_with("undefined instruction"));
+    }
+}
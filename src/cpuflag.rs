@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ArmV6m {
     pub result: u32,
     pub n: u32,
@@ -9,23 +9,10 @@ pub struct ArmV6m {
     pub apsr: u32,
 }
 
-impl Default for ArmV6m {
-    fn default() -> Self {
-        Self {
-            result: 0,
-            n: 0,
-            z: 0,
-            c: 0,
-            v: 0,
-            q: 0,
-            apsr: 0,
-        }
-    }
-}
-
 pub trait CalcFlags {
-    fn new(apsr: u32) -> ArmV6m;
+    fn new(apsr: u32) -> Self;
     fn flags_to_apsr(&self) -> u32;
+    fn set_nz(&mut self, result: u32);
     fn cond(&self, cond: u32) -> (bool, String);
 }
 
@@ -38,7 +25,7 @@ impl CalcFlags for ArmV6m {
             c: (apsr >> 29) & 0b1,
             v: (apsr >> 28) & 0b1,
             q: (apsr >> 27) & 0b1,
-            apsr: apsr,
+            apsr,
         }
     }
 
@@ -49,6 +36,12 @@ impl CalcFlags for ArmV6m {
         apsr
     }
 
+    fn set_nz(&mut self, result: u32) {
+        self.result = result;
+        self.n = (result >> 31) & 0b1;
+        self.z = (result == 0) as u32;
+    }
+
     fn cond(&self, cond: u32) -> (bool, String) {
         match cond & 0b1111 {
             0b0000 => (self.z == 1, "eq".to_string()),
@@ -60,18 +53,18 @@ impl CalcFlags for ArmV6m {
             0b0110 => (self.v == 1, "vs".to_string()),
             0b0111 => (self.v == 0, "vc".to_string()),
             0b1000 => (self.c == 1 && self.z == 0, "hi".to_string()),
-            0b1001 => (self.c == 0 && self.z == 1, "ls".to_string()),
+            0b1001 => (self.c == 0 || self.z == 1, "ls".to_string()),
             0b1010 => (self.n == self.v, "ge".to_string()),
             0b1011 => (self.n != self.v, "lt".to_string()),
-            0b1100 => (self.z == 0 && self.n != self.v, "gt".to_string()),
-            0b1101 => (self.z == 1 && self.n == self.v, "le".to_string()),
+            0b1100 => (self.z == 0 && self.n == self.v, "gt".to_string()),
+            0b1101 => (self.z == 1 || self.n != self.v, "le".to_string()),
             0b1110 => (true, "al".to_string()),
             _ => (false, "*UNDEFINED*".to_string()),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct IfThenFlags {
     pub cond: u32,
     pub encode: u32,
@@ -79,19 +72,8 @@ pub struct IfThenFlags {
     pub flags: ArmV6m,
 }
 
-impl Default for IfThenFlags {
-    fn default() -> Self {
-        Self {
-            cond: 0,
-            encode: 0,
-            epsr: 0,
-            flags: { ArmV6m::default() },
-        }
-    }
-}
-
 pub trait IfThenCtrl {
-    fn new(apsr: u32, epsr: u32) -> IfThenFlags;
+    fn new(apsr: u32, epsr: u32) -> Self;
     fn in_it_block(&self) -> bool;
     fn last_in_it_block(&self) -> bool;
     fn update_epsr(&mut self) -> u32;
@@ -109,8 +91,8 @@ impl IfThenCtrl for IfThenFlags {
         }
         IfThenFlags {
             cond: (itstate >> 5) & 0b111,
-            encode: encode,
-            epsr: epsr,
+            encode,
+            epsr,
             flags: ArmV6m {
                 ..ArmV6m::new(apsr)
             },
@@ -145,26 +127,60 @@ impl IfThenCtrl for IfThenFlags {
     }
 }
 
+// AddWithCarry() of the ARM pseudocode
 pub fn add_with_carry(a: u32, b: u32, carry: u32) -> ArmV6m {
-    let a64: i64 = a as i64;
-    let b64: i64 = b as i64;
-    let c64: i64 = carry as i64;
-    let r64: i64 = a64 + b64 + c64;
+    let unsigned_sum: u64 = (a as u64) + (b as u64) + (carry as u64);
+    let signed_sum: i64 = (a as i32 as i64) + (b as i32 as i64) + (carry as i64);
     let mut r: ArmV6m = ArmV6m::default();
 
-    r.result = (r64 & 0xffffffff) as u32;
-    match r64 {
-        val if val < 0 => r.n = 1,
-        val if val == 0 => r.z = 1,
-        val if (val & 0x00010000) != 0 => r.c = 1,
-        val if (a & 0x80000000) != (val & 0x80000000) as u32 => r.v = 1,
-        _ => (),
-    }
+    r.result = unsigned_sum as u32;
+    r.n = r.result >> 31;
+    r.z = (r.result == 0) as u32;
+    r.c = (r.result as u64 != unsigned_sum) as u32;
+    r.v = (r.result as i32 as i64 != signed_sum) as u32;
     r.apsr = (r.n << 31) | (r.z << 30) | (r.c << 29) | (r.v << 28) | (r.q << 27);
     println!("\t Result:{:08x}\n\t {:?}", r.result, r);
     r
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShiftType {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+}
+
+// Shift_C() of the ARM pseudocode: returns (result, carry_out)
+pub fn shift_with_carry(value: u32, shift_type: ShiftType, amount: u32, carry: u32) -> (u32, u32) {
+    if amount == 0 {
+        return (value, carry);
+    }
+    match shift_type {
+        ShiftType::Lsl => match amount {
+            1..=31 => (value << amount, (value >> (32 - amount)) & 0b1),
+            32 => (0, value & 0b1),
+            _ => (0, 0),
+        },
+        ShiftType::Lsr => match amount {
+            1..=31 => (value >> amount, (value >> (amount - 1)) & 0b1),
+            32 => (0, value >> 31),
+            _ => (0, 0),
+        },
+        ShiftType::Asr => match amount {
+            1..=31 => (
+                ((value as i32) >> amount) as u32,
+                (value >> (amount - 1)) & 0b1,
+            ),
+            _ => (((value as i32) >> 31) as u32, value >> 31),
+        },
+        ShiftType::Ror => {
+            let result: u32 = value.rotate_right(amount % 32);
+            (result, result >> 31)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -347,14 +363,14 @@ mod tests {
         let mut flag: ArmV6m = { ArmV6m::new(0) };
         flag.z = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.z = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
     }
 
     #[test]
@@ -364,14 +380,14 @@ mod tests {
         let mut flag: ArmV6m = { ArmV6m::new(0) };
         flag.c = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.c = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
     }
 
     #[test]
@@ -381,14 +397,14 @@ mod tests {
         let mut flag: ArmV6m = { ArmV6m::new(0) };
         flag.n = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.n = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
     }
 
     #[test]
@@ -398,14 +414,14 @@ mod tests {
         let mut flag: ArmV6m = { ArmV6m::new(0) };
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
     }
 
     #[test]
@@ -416,27 +432,27 @@ mod tests {
         flag.z = 0;
         flag.c = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         flag.z = 0;
         flag.c = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         flag.z = 1;
         flag.c = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.z = 1;
         flag.c = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
     }
 
     #[test]
@@ -447,27 +463,27 @@ mod tests {
         flag.n = 0;
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         flag.n = 0;
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.n = 1;
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.n = 1;
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
     }
 
     #[test]
@@ -479,59 +495,211 @@ mod tests {
         flag.n = 0;
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         flag.z = 0;
         flag.n = 0;
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         flag.z = 0;
         flag.n = 1;
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, true);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         flag.z = 0;
         flag.n = 1;
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
-
+        assert!(!tf);
         flag.z = 1;
         flag.n = 0;
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
         flag.z = 1;
         flag.n = 0;
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         flag.z = 1;
         flag.n = 1;
         flag.v = 0;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, false);
+        assert!(tf);
         flag.z = 1;
         flag.n = 1;
         flag.v = 1;
         let (tf, _) = flag.cond(test_cond);
-        assert_eq!(tf, false);
+        assert!(!tf);
         let (tf, _) = flag.cond(test_not_cond);
-        assert_eq!(tf, true);
+        assert!(tf);
+    }
+
+    #[test]
+    fn test_arm_v6_flags_9() {
+        // a, b, carry, result, n, z, c, v
+        let test_table = [
+            (0x00000000, 0x00000000, 0, 0x00000000, 0, 1, 0, 0),
+            (0x00000000, 0x00000000, 1, 0x00000001, 0, 0, 0, 0),
+            (0x00000001, 0xffffffff, 0, 0x00000000, 0, 1, 1, 0),
+            (0xffffffff, 0x00000000, 1, 0x00000000, 0, 1, 1, 0),
+            (0xffffffff, 0xffffffff, 1, 0xffffffff, 1, 0, 1, 0),
+            (0x7fffffff, 0x00000001, 0, 0x80000000, 1, 0, 0, 1),
+            (0x7fffffff, 0x00000000, 1, 0x80000000, 1, 0, 0, 1),
+            (0x7fffffff, 0x7fffffff, 1, 0xffffffff, 1, 0, 0, 1),
+            (0x80000000, 0x80000000, 0, 0x00000000, 0, 1, 1, 1),
+            (0x80000000, 0xffffffff, 0, 0x7fffffff, 0, 0, 1, 1),
+            (0x80000000, 0x7fffffff, 0, 0xffffffff, 1, 0, 0, 0),
+            (0x0000ffff, 0x00000001, 0, 0x00010000, 0, 0, 0, 0),
+            // subtraction: a + NOT(b) + 1
+            (0x00000005, !0x00000005, 1, 0x00000000, 0, 1, 1, 0),
+            (0x00000005, !0x00000006, 1, 0xffffffff, 1, 0, 0, 0),
+            (0x80000000, !0x00000001, 1, 0x7fffffff, 0, 0, 1, 1),
+            (0x00000000, !0x80000000, 1, 0x80000000, 1, 0, 0, 1),
+        ];
+        for (a, b, carry, result, n, z, c, v) in test_table.iter() {
+            let r: ArmV6m = add_with_carry(*a, *b, *carry);
+            assert_eq!(r.result, *result);
+            assert_eq!(r.n, *n);
+            assert_eq!(r.z, *z);
+            assert_eq!(r.c, *c);
+            assert_eq!(r.v, *v);
+            assert_eq!(r.q, 0);
+            assert_eq!(r.apsr, (n << 31) | (z << 30) | (c << 29) | (v << 28));
+            assert_eq!(r.flags_to_apsr(), r.apsr);
+        }
+    }
+
+    #[test]
+    fn test_arm_v6_flags_10() {
+        // every flag is derived independently from the others
+        for a in [0u32, 1, 0x7fffffff, 0x80000000, 0xfffffffe, 0xffffffff].iter() {
+            for b in [0u32, 1, 0x7fffffff, 0x80000000, 0xfffffffe, 0xffffffff].iter() {
+                for carry in 0..2 {
+                    let r: ArmV6m = add_with_carry(*a, *b, carry);
+                    let unsigned_sum: u64 = (*a as u64) + (*b as u64) + (carry as u64);
+                    let signed_sum: i64 = (*a as i32 as i64) + (*b as i32 as i64) + (carry as i64);
+                    assert_eq!(r.result, a.wrapping_add(*b).wrapping_add(carry));
+                    assert_eq!(r.n, (r.result >> 31) & 0b1);
+                    assert_eq!(r.z == 1, r.result == 0);
+                    assert_eq!(r.c == 1, unsigned_sum > 0xffffffff);
+                    assert_eq!(
+                        r.v == 1,
+                        signed_sum > i32::MAX as i64 || signed_sum < i32::MIN as i64
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shift_with_carry_1() {
+        // shift by 0 passes the carry through
+        for shift_type in [
+            ShiftType::Lsl,
+            ShiftType::Lsr,
+            ShiftType::Asr,
+            ShiftType::Ror,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                shift_with_carry(0x80000001, *shift_type, 0, 0),
+                (0x80000001, 0)
+            );
+            assert_eq!(
+                shift_with_carry(0x80000001, *shift_type, 0, 1),
+                (0x80000001, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_shift_with_carry_2() {
+        assert_eq!(
+            shift_with_carry(0x80000001, ShiftType::Lsl, 1, 0),
+            (0x00000002, 1)
+        );
+        assert_eq!(
+            shift_with_carry(0x40000000, ShiftType::Lsl, 1, 1),
+            (0x80000000, 0)
+        );
+        assert_eq!(
+            shift_with_carry(0x00000001, ShiftType::Lsl, 31, 0),
+            (0x80000000, 0)
+        );
+        assert_eq!(shift_with_carry(0x00000003, ShiftType::Lsl, 32, 0), (0, 1));
+        assert_eq!(shift_with_carry(0xffffffff, ShiftType::Lsl, 33, 1), (0, 0));
+    }
+
+    #[test]
+    fn test_shift_with_carry_3() {
+        assert_eq!(
+            shift_with_carry(0x80000001, ShiftType::Lsr, 1, 0),
+            (0x40000000, 1)
+        );
+        assert_eq!(
+            shift_with_carry(0x80000000, ShiftType::Lsr, 31, 0),
+            (0x00000001, 0)
+        );
+        assert_eq!(shift_with_carry(0x80000000, ShiftType::Lsr, 32, 0), (0, 1));
+        assert_eq!(shift_with_carry(0x7fffffff, ShiftType::Lsr, 32, 1), (0, 0));
+        assert_eq!(shift_with_carry(0xffffffff, ShiftType::Lsr, 255, 1), (0, 0));
+    }
+
+    #[test]
+    fn test_shift_with_carry_4() {
+        assert_eq!(
+            shift_with_carry(0x80000001, ShiftType::Asr, 1, 0),
+            (0xc0000000, 1)
+        );
+        assert_eq!(
+            shift_with_carry(0x40000000, ShiftType::Asr, 30, 0),
+            (0x00000001, 0)
+        );
+        assert_eq!(
+            shift_with_carry(0x80000000, ShiftType::Asr, 32, 0),
+            (0xffffffff, 1)
+        );
+        assert_eq!(shift_with_carry(0x7fffffff, ShiftType::Asr, 32, 1), (0, 0));
+        assert_eq!(
+            shift_with_carry(0x80000000, ShiftType::Asr, 200, 0),
+            (0xffffffff, 1)
+        );
+    }
+
+    #[test]
+    fn test_shift_with_carry_5() {
+        assert_eq!(
+            shift_with_carry(0x00000001, ShiftType::Ror, 1, 0),
+            (0x80000000, 1)
+        );
+        assert_eq!(
+            shift_with_carry(0x00000002, ShiftType::Ror, 1, 1),
+            (0x00000001, 0)
+        );
+        assert_eq!(
+            shift_with_carry(0x80000001, ShiftType::Ror, 32, 0),
+            (0x80000001, 1)
+        );
+        assert_eq!(
+            shift_with_carry(0x00000010, ShiftType::Ror, 36, 0),
+            (0x00000001, 0)
+        );
     }
 
     #[test]
@@ -1,11 +1,11 @@
 pub fn b32_fmt(bin: u32) -> String {
     let mut bin_tmp: u32 = bin;
     let mut bin_str: String = format!("{:04b}", bin_tmp & 0b1111);
-    bin_tmp = bin_tmp >> 4;
+    bin_tmp >>= 4;
 
     for _i in 0..7 {
         bin_str = format!("{:04b}_{}", bin_tmp & 0b1111, bin_str);
-        bin_tmp = bin_tmp >> 4;
+        bin_tmp >>= 4;
     }
     bin_str
 }
@@ -13,11 +13,37 @@ pub fn b32_fmt(bin: u32) -> String {
 pub fn b16_fmt(bin: u16) -> String {
     let mut bin_tmp: u16 = bin;
     let mut bin_str: String = format!("{:04b}", bin_tmp & 0b1111);
-    bin_tmp = bin_tmp >> 4;
+    bin_tmp >>= 4;
 
     for _i in 0..3 {
         bin_str = format!("{:04b}_{}", bin_tmp & 0b1111, bin_str);
-        bin_tmp = bin_tmp >> 4;
+        bin_tmp >>= 4;
     }
     bin_str
 }
+
+pub fn reg_fmt(regnum: usize) -> String {
+    match regnum {
+        13 => "sp".to_string(),
+        14 => "lr".to_string(),
+        15 => "pc".to_string(),
+        _ => format!("r{}", regnum),
+    }
+}
+
+pub fn sysm_fmt(sysm: u32) -> String {
+    match sysm {
+        0 => "apsr".to_string(),
+        1 => "iapsr".to_string(),
+        2 => "eapsr".to_string(),
+        3 => "xpsr".to_string(),
+        5 => "ipsr".to_string(),
+        6 => "epsr".to_string(),
+        7 => "iepsr".to_string(),
+        8 => "msp".to_string(),
+        9 => "psp".to_string(),
+        16 => "primask".to_string(),
+        20 => "control".to_string(),
+        _ => format!("sysm{}", sysm),
+    }
+}
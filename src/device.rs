@@ -1,3 +1,6 @@
+use crate::nvic::Nvic;
+use crate::systick::SysTick;
+
 #[derive(Debug)]
 pub struct DeviceMapping {
     pub adrs: u32,
@@ -18,20 +21,16 @@ pub trait DeviceAccess {
     fn set_range(&mut self, range: DeviceMapping);
     fn is_mapped(&self, pt: u32) -> bool {
         let mapping = self.get_range();
-        if mapping.adrs <= pt && ((pt - mapping.adrs) as usize) < mapping.size {
-            true
-        } else {
-            false
-        }
+        mapping.adrs <= pt && ((pt - mapping.adrs) as usize) < mapping.size
     }
 
     fn read8(&self, adrs: u32) -> Option<u8>;
     fn read16(&self, adrs: u32) -> Option<u16>;
     fn read32(&self, adrs: u32) -> Option<u32>;
 
-    fn write8(&mut self, adrs: u32, val: u8);
-    fn write16(&mut self, adrs: u32, val: u16);
-    fn write32(&mut self, adrs: u32, val: u32);
+    fn write8(&mut self, adrs: u32, val: u8) -> Option<()>;
+    fn write16(&mut self, adrs: u32, val: u16) -> Option<()>;
+    fn write32(&mut self, adrs: u32, val: u32) -> Option<()>;
 }
 
 impl DeviceAccess for MemoryMappedDevice {
@@ -53,63 +52,89 @@ impl DeviceAccess for MemoryMappedDevice {
     }
 
     fn read16(&self, adrs: u32) -> Option<u16> {
-        if self.readable {
-            if self.is_mapped(adrs) && self.is_mapped(adrs + 1) {
-                let index: usize = (adrs - self.mapping.adrs) as usize;
-                let data16: u16 =
-                    ((self.data[index + 1] as u16) << 8) | ((self.data[index]) as u16);
-                return Some(data16);
-            }
+        if self.readable && self.is_mapped(adrs) && self.is_mapped(adrs + 1) {
+            let index: usize = (adrs - self.mapping.adrs) as usize;
+            let data16: u16 = ((self.data[index + 1] as u16) << 8) | ((self.data[index]) as u16);
+            return Some(data16);
         }
         None
     }
 
     fn read32(&self, adrs: u32) -> Option<u32> {
-        if self.readable {
-            if self.is_mapped(adrs) && self.is_mapped(adrs + 3) {
-                let index: usize = (adrs - self.mapping.adrs) as usize;
-                let data32: u32 = ((self.data[index + 3] as u32) << 24)
-                    | (((self.data[index + 2]) as u32) << 16)
-                    | (((self.data[index + 1]) as u32) << 8)
-                    | ((self.data[index]) as u32);
-                return Some(data32);
-            }
+        if self.readable && self.is_mapped(adrs) && self.is_mapped(adrs + 3) {
+            let index: usize = (adrs - self.mapping.adrs) as usize;
+            let data32: u32 = ((self.data[index + 3] as u32) << 24)
+                | (((self.data[index + 2]) as u32) << 16)
+                | (((self.data[index + 1]) as u32) << 8)
+                | ((self.data[index]) as u32);
+            return Some(data32);
         }
         None
     }
 
-    fn write8(&mut self, adrs: u32, val: u8) {
+    fn write8(&mut self, adrs: u32, val: u8) -> Option<()> {
         if self.writable && self.is_mapped(adrs) {
             let index: usize = (adrs - self.mapping.adrs) as usize;
             self.data[index] = val;
+            return Some(());
         }
+        None
     }
 
-    fn write16(&mut self, adrs: u32, val: u16) {
-        if self.writable {
-            if self.is_mapped(adrs) && self.is_mapped(adrs + 1) {
-                let index: usize = (adrs - self.mapping.adrs) as usize;
-                self.data[index] = (val & 0xff) as u8;
-                self.data[index + 1] = ((val >> 8) & 0xff) as u8;
-            }
+    fn write16(&mut self, adrs: u32, val: u16) -> Option<()> {
+        if self.writable && self.is_mapped(adrs) && self.is_mapped(adrs + 1) {
+            let index: usize = (adrs - self.mapping.adrs) as usize;
+            self.data[index] = (val & 0xff) as u8;
+            self.data[index + 1] = ((val >> 8) & 0xff) as u8;
+            return Some(());
         }
+        None
     }
 
-    fn write32(&mut self, adrs: u32, val: u32) {
-        if self.writable {
-            if self.is_mapped(adrs) && self.is_mapped(adrs + 3) {
-                let index: usize = (adrs - self.mapping.adrs) as usize;
-                self.data[index] = (val & 0xff) as u8;
-                self.data[index + 1] = ((val >> 8) & 0xff) as u8;
-                self.data[index + 2] = ((val >> 16) & 0xff) as u8;
-                self.data[index + 3] = ((val >> 24) & 0xff) as u8;
-            }
+    fn write32(&mut self, adrs: u32, val: u32) -> Option<()> {
+        if self.writable && self.is_mapped(adrs) && self.is_mapped(adrs + 3) {
+            let index: usize = (adrs - self.mapping.adrs) as usize;
+            self.data[index] = (val & 0xff) as u8;
+            self.data[index + 1] = ((val >> 8) & 0xff) as u8;
+            self.data[index + 2] = ((val >> 16) & 0xff) as u8;
+            self.data[index + 3] = ((val >> 24) & 0xff) as u8;
+            return Some(());
         }
+        None
     }
 }
 
 pub struct SystemMap {
     pub map: Vec<MemoryMappedDevice>,
+    pub nvic: Nvic,
+    pub systick: SysTick,
+}
+
+impl SystemMap {
+    pub fn new() -> SystemMap {
+        SystemMap {
+            map: Vec::new(),
+            nvic: Nvic::default(),
+            systick: SysTick::default(),
+        }
+    }
+
+    // devices of the private peripheral bus which are always present
+    fn get_core_device(&mut self, pt: u32) -> Option<(&str, &mut dyn DeviceAccess)> {
+        if self.nvic.is_mapped(pt) {
+            Some(("NVIC", &mut self.nvic))
+        } else if self.systick.is_mapped(pt) {
+            Some(("SysTick", &mut self.systick))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for SystemMap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub trait SystemMapAccess<'b> {
@@ -120,9 +145,9 @@ pub trait SystemMapAccess<'b> {
     fn read16(&mut self, adrs: u32) -> Result<u16, String>;
     fn read32(&mut self, adrs: u32) -> Result<u32, String>;
 
-    fn write8(&mut self, adrs: u32, val: u8);
-    fn write16(&mut self, adrs: u32, val: u16);
-    fn write32(&mut self, adrs: u32, val: u32);
+    fn write8(&mut self, adrs: u32, val: u8) -> Result<(), String>;
+    fn write16(&mut self, adrs: u32, val: u16) -> Result<(), String>;
+    fn write32(&mut self, adrs: u32, val: u32) -> Result<(), String>;
 }
 
 impl<'b> SystemMapAccess<'b> for SystemMap {
@@ -139,7 +164,7 @@ impl<'b> SystemMapAccess<'b> for SystemMap {
                 println!("*");
                 return Some(dev);
             } else {
-                println!("");
+                println!();
             }
         }
         None
@@ -154,6 +179,12 @@ impl<'b> SystemMapAccess<'b> for SystemMap {
     }
 
     fn read8(&mut self, adrs: u32) -> Result<u8, String> {
+        if let Some((name, dev)) = self.get_core_device(adrs) {
+            return dev.read8(adrs).ok_or(format!(
+                "Error: read8(): can not access to {:08x} in {}",
+                adrs, name
+            ));
+        }
         match self.get_device(adrs) {
             Some(x) => match x.read8(adrs) {
                 Some(data) => Ok(data),
@@ -170,6 +201,12 @@ impl<'b> SystemMapAccess<'b> for SystemMap {
     }
 
     fn read16(&mut self, adrs: u32) -> Result<u16, String> {
+        if let Some((name, dev)) = self.get_core_device(adrs) {
+            return dev.read16(adrs).ok_or(format!(
+                "Error: read16(): can not access to {:08x} in {}",
+                adrs, name
+            ));
+        }
         match self.get_device(adrs) {
             Some(x) => match x.read16(adrs) {
                 Some(data) => Ok(data),
@@ -186,45 +223,90 @@ impl<'b> SystemMapAccess<'b> for SystemMap {
     }
 
     fn read32(&mut self, adrs: u32) -> Result<u32, String> {
+        if let Some((name, dev)) = self.get_core_device(adrs) {
+            return dev.read32(adrs).ok_or(format!(
+                "Error: read32(): can not access to {:08x} in {}",
+                adrs, name
+            ));
+        }
         match self.get_device(adrs) {
             Some(x) => match x.read32(adrs) {
-                Some(data) => {
-                    return Ok(data);
-                }
-                None => {
-                    return Err(format!(
-                        "Error: read32(): can not access to {:08x} in {}",
-                        adrs, x.name
-                    ));
-                }
+                Some(data) => Ok(data),
+                None => Err(format!(
+                    "Error: read32(): can not access to {:08x} in {}",
+                    adrs, x.name
+                )),
             },
-            None => {
-                return Err(format!(
-                    "Error: read32(): no devices are assigned:{:08x}",
-                    adrs
-                ));
-            }
+            None => Err(format!(
+                "Error: read32(): no devices are assigned:{:08x}",
+                adrs
+            )),
         }
     }
 
-    fn write8(&mut self, adrs: u32, val: u8) {
+    fn write8(&mut self, adrs: u32, val: u8) -> Result<(), String> {
+        if let Some((name, dev)) = self.get_core_device(adrs) {
+            return dev.write8(adrs, val).ok_or(format!(
+                "Error: write8(): can not access to {:08x} in {}",
+                adrs, name
+            ));
+        }
         match self.get_device(adrs) {
-            Some(x) => x.write8(adrs, val),
-            None => (),
+            Some(x) => match x.write8(adrs, val) {
+                Some(()) => Ok(()),
+                None => Err(format!(
+                    "Error: write8(): can not access to {:08x} in {}",
+                    adrs, x.name
+                )),
+            },
+            None => Err(format!(
+                "Error: write8(): no devices are assigned:{:08x}",
+                adrs
+            )),
         }
     }
 
-    fn write16(&mut self, adrs: u32, val: u16) {
+    fn write16(&mut self, adrs: u32, val: u16) -> Result<(), String> {
+        if let Some((name, dev)) = self.get_core_device(adrs) {
+            return dev.write16(adrs, val).ok_or(format!(
+                "Error: write16(): can not access to {:08x} in {}",
+                adrs, name
+            ));
+        }
         match self.get_device(adrs) {
-            Some(x) => x.write16(adrs, val),
-            None => (),
+            Some(x) => match x.write16(adrs, val) {
+                Some(()) => Ok(()),
+                None => Err(format!(
+                    "Error: write16(): can not access to {:08x} in {}",
+                    adrs, x.name
+                )),
+            },
+            None => Err(format!(
+                "Error: write16(): no devices are assigned:{:08x}",
+                adrs
+            )),
         }
     }
 
-    fn write32(&mut self, adrs: u32, val: u32) {
+    fn write32(&mut self, adrs: u32, val: u32) -> Result<(), String> {
+        if let Some((name, dev)) = self.get_core_device(adrs) {
+            return dev.write32(adrs, val).ok_or(format!(
+                "Error: write32(): can not access to {:08x} in {}",
+                adrs, name
+            ));
+        }
         match self.get_device(adrs) {
-            Some(x) => x.write32(adrs, val),
-            None => (),
+            Some(x) => match x.write32(adrs, val) {
+                Some(()) => Ok(()),
+                None => Err(format!(
+                    "Error: write32(): can not access to {:08x} in {}",
+                    adrs, x.name
+                )),
+            },
+            None => Err(format!(
+                "Error: write32(): no devices are assigned:{:08x}",
+                adrs
+            )),
         }
     }
 }
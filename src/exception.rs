@@ -0,0 +1,343 @@
+use crate::cpu::FaultAction;
+use crate::cpu::M0System;
+use crate::cpu::RunState;
+use crate::cpu::EPSR_T;
+use crate::cpu::LOCKUP_ADDRESS;
+use crate::instruction::bus_error;
+use crate::instruction::unpredicable;
+use crate::nvic::IRQ_LINES;
+
+// exception numbers
+pub const EXC_RESET: usize = 1;
+pub const EXC_NMI: usize = 2;
+pub const EXC_HARDFAULT: usize = 3;
+pub const EXC_SVCALL: usize = 11;
+pub const EXC_PENDSV: usize = 14;
+pub const EXC_SYSTICK: usize = 15;
+pub const EXC_IRQ0: usize = 16;
+pub const EXC_MAX_IRQ: usize = EXC_IRQ0 + IRQ_LINES - 1;
+pub const EXC_MAX: usize = EXC_MAX_IRQ + 1;
+
+// pending bits of the system exceptions
+pub const ICSR_NMIPENDSET: u32 = 0b1 << 31;
+pub const ICSR_PENDSVSET: u32 = 0b1 << 28;
+pub const ICSR_PENDSTSET: u32 = 0b1 << 26;
+pub const SHCSR_SVCALLPENDED: u32 = 0b1 << 15;
+
+// execution priority of thread mode without any active exceptions
+pub const PRIORITY_THREAD: i32 = 256;
+
+pub const EXCEPTION_ENTRY_CYCLES: u32 = 16;
+pub const EXCEPTION_RETURN_CYCLES: u32 = 16;
+pub const EXCEPTION_TAIL_CHAIN_CYCLES: u32 = 6;
+
+// cycles of the entry before the vector fetch, a higher priority exception
+// raised meanwhile arrives late and takes over the stacked frame
+pub const LATE_ARRIVAL_CYCLES: u32 = 12;
+
+pub fn current_mode_is_handler(system: &M0System) -> bool {
+    system.cpu.ipsr & 0x3f != 0
+}
+
+pub fn xpsr(system: &M0System) -> u32 {
+    (system.cpu.apsr & 0xf0000000) | (system.cpu.epsr & EPSR_T) | (system.cpu.ipsr & 0x3f)
+}
+
+pub fn set_exception_pending(system: &mut M0System, exception_number: usize) {
+    match exception_number {
+        EXC_NMI => system.cpu.icsr |= ICSR_NMIPENDSET,
+        EXC_SVCALL => system.cpu.shcsr |= SHCSR_SVCALLPENDED,
+        EXC_PENDSV => system.cpu.icsr |= ICSR_PENDSVSET,
+        EXC_SYSTICK => system.cpu.icsr |= ICSR_PENDSTSET,
+        EXC_IRQ0..=EXC_MAX_IRQ => system
+            .system_map
+            .nvic
+            .raise_irq(exception_number - EXC_IRQ0),
+        _ => println!("\t (can not pend exception {})", exception_number),
+    }
+}
+
+pub fn clear_exception_pending(system: &mut M0System, exception_number: usize) {
+    match exception_number {
+        EXC_NMI => system.cpu.icsr &= !ICSR_NMIPENDSET,
+        EXC_SVCALL => system.cpu.shcsr &= !SHCSR_SVCALLPENDED,
+        EXC_PENDSV => system.cpu.icsr &= !ICSR_PENDSVSET,
+        EXC_SYSTICK => system.cpu.icsr &= !ICSR_PENDSTSET,
+        EXC_IRQ0..=EXC_MAX_IRQ => system
+            .system_map
+            .nvic
+            .clear_irq(exception_number - EXC_IRQ0),
+        _ => (),
+    }
+}
+
+pub fn is_exception_pending(system: &M0System, exception_number: usize) -> bool {
+    match exception_number {
+        EXC_NMI => system.cpu.icsr & ICSR_NMIPENDSET != 0,
+        EXC_SVCALL => system.cpu.shcsr & SHCSR_SVCALLPENDED != 0,
+        EXC_PENDSV => system.cpu.icsr & ICSR_PENDSVSET != 0,
+        EXC_SYSTICK => system.cpu.icsr & ICSR_PENDSTSET != 0,
+        EXC_IRQ0..=EXC_MAX_IRQ => system
+            .system_map
+            .nvic
+            .is_pending(exception_number - EXC_IRQ0),
+        _ => false,
+    }
+}
+
+// priority of an exception: negative values are fixed priorities,
+// the others are the upper 2 bits of the 8 bit priority field
+pub fn exception_priority(system: &M0System, exception_number: usize) -> i32 {
+    match exception_number {
+        EXC_RESET => -3,
+        EXC_NMI => -2,
+        EXC_HARDFAULT => -1,
+        EXC_SVCALL => ((system.cpu.shpr2 >> 24) & 0xc0) as i32,
+        EXC_PENDSV => ((system.cpu.shpr3 >> 16) & 0xc0) as i32,
+        EXC_SYSTICK => ((system.cpu.shpr3 >> 24) & 0xc0) as i32,
+        EXC_IRQ0..=EXC_MAX_IRQ => {
+            system.system_map.nvic.priority[exception_number - EXC_IRQ0] as i32
+        }
+        _ => PRIORITY_THREAD,
+    }
+}
+
+// ExecutionPriority() of the ARM pseudocode
+pub fn execution_priority(system: &M0System) -> i32 {
+    let mut highestpri: i32 = PRIORITY_THREAD;
+    for (n, active) in system.cpu.exception_active.iter().enumerate() {
+        if *active {
+            highestpri = highestpri.min(exception_priority(system, n));
+        }
+    }
+    if system.cpu.primask_pm == 1 {
+        highestpri = highestpri.min(0);
+    }
+    highestpri
+}
+
+// the pending exception with the highest priority (the lowest number wins a tie)
+pub fn pending_exception(system: &M0System) -> Option<usize> {
+    let mut pending: Option<(usize, i32)> = None;
+    for n in 0..EXC_MAX {
+        if is_exception_pending(system, n) {
+            let priority: i32 = exception_priority(system, n);
+            match pending {
+                Some((_, current)) if current <= priority => (),
+                _ => pending = Some((n, priority)),
+            }
+        }
+    }
+    pending.map(|(n, _)| n)
+}
+
+// takes the pending exception if its priority is high enough to preempt
+// None when nothing is taken, a failed entry returns Some(0)
+pub fn check_exception(system: &mut M0System) -> Option<u32> {
+    match pending_exception(system) {
+        Some(n) if exception_priority(system, n) < execution_priority(system) => {
+            clear_exception_pending(system, n);
+            Some(exception_entry(system, n))
+        }
+        _ => None,
+    }
+}
+
+pub fn exception_entry(system: &mut M0System, exception_number: usize) -> u32 {
+    println!("\t EXCEPTION ENTRY: {}", exception_number);
+    if let Err(e) = push_stack(system, exception_number) {
+        return entry_fault(e, system, exception_number);
+    }
+    system.tick(LATE_ARRIVAL_CYCLES);
+    let exception_number: usize = late_arrival(system, exception_number);
+    if let Err(e) = exception_taken(system, exception_number) {
+        return entry_fault(e, system, exception_number);
+    }
+    EXCEPTION_ENTRY_CYCLES
+}
+
+// the exception being entered is pended again when a higher priority one arrives
+fn late_arrival(system: &mut M0System, exception_number: usize) -> usize {
+    if exception_number == EXC_HARDFAULT {
+        return exception_number;
+    }
+    match pending_exception(system) {
+        Some(n) if exception_priority(system, n) < exception_priority(system, exception_number) => {
+            println!("\t LATE ARRIVAL: {}", n);
+            clear_exception_pending(system, n);
+            set_exception_pending(system, exception_number);
+            n
+        }
+        _ => exception_number,
+    }
+}
+
+// a fault on the entry of HardFault or NMI can not be escalated any further
+fn entry_fault(message: String, system: &mut M0System, exception_number: usize) -> u32 {
+    if exception_number == EXC_HARDFAULT || exception_number == EXC_NMI {
+        println!("\t BUS ERROR: {}", message);
+        record_fault(
+            &format!("bus error on entry of exception {}", exception_number),
+            system,
+        );
+        return lockup(system);
+    }
+    bus_error(message, system)
+}
+
+fn record_fault(reason: &str, system: &mut M0System) {
+    let fault: String = format!(
+        "{} at {:08x} in exception {}",
+        reason,
+        system.cpu.pc,
+        system.cpu.ipsr & 0x3f
+    );
+    system.fault_chain.push(fault);
+}
+
+// synchronous faults are taken as HardFault with the faulting instruction stacked
+pub fn hard_fault(reason: &str, system: &mut M0System) -> u32 {
+    println!("\t HARDFAULT: {} at {:08x}", reason, system.cpu.pc);
+    record_fault(reason, system);
+    if system.fault_action == FaultAction::Stop {
+        return 0;
+    }
+    if execution_priority(system) < 0 {
+        return lockup(system);
+    }
+    exception_entry(system, EXC_HARDFAULT)
+}
+
+// the processor stops fetching at 0xfffffffe until it is reset
+pub fn lockup(system: &mut M0System) -> u32 {
+    println!("\t LOCKUP: fault in NMI or HardFault handler");
+    system.state = RunState::Lockup;
+    system.cpu.pc = LOCKUP_ADDRESS;
+    0
+}
+
+// PushStack() of the ARM pseudocode
+fn push_stack(system: &mut M0System, exception_number: usize) -> Result<(), String> {
+    let spsel: usize = if current_mode_is_handler(system) {
+        0
+    } else {
+        system.cpu.ctrl_spsel
+    };
+    let frameptralign: u32 = (system.cpu.sp[spsel] >> 2) & 0b1;
+    let frameptr: u32 = system.cpu.sp[spsel].wrapping_sub(0x20) & !0b111;
+    let xpsr_frame: u32 = (xpsr(system) & !(0b1 << 9)) | (frameptralign << 9);
+    let frame: [u32; 8] = [
+        system.cpu.r[0],
+        system.cpu.r[1],
+        system.cpu.r[2],
+        system.cpu.r[3],
+        system.cpu.r[12],
+        system.cpu.lr,
+        system.cpu.pc,
+        xpsr_frame,
+    ];
+    for (i, val) in frame.iter().enumerate() {
+        system.write32(frameptr.wrapping_add(4 * i as u32), *val)?;
+    }
+    system.cpu.sp[spsel] = frameptr;
+    // kept for the lockup report, the handler may move the SP before faulting
+    if exception_number == EXC_HARDFAULT {
+        system.fault_frame = Some((spsel, frameptr));
+    }
+    system.cpu.lr = if current_mode_is_handler(system) {
+        0xfffffff1
+    } else if spsel == 0 {
+        0xfffffff9
+    } else {
+        0xfffffffd
+    };
+    Ok(())
+}
+
+// ExceptionTaken() of the ARM pseudocode
+fn exception_taken(system: &mut M0System, exception_number: usize) -> Result<(), String> {
+    let vector: u32 = system.read32(system.cpu.vtor + 4 * exception_number as u32)?;
+    system.cpu.pc = vector & 0xfffffffe;
+    system.cpu.epsr = (vector & 0b1) << 24;
+    system.cpu.ipsr = exception_number as u32;
+    system.cpu.ctrl_spsel = 0;
+    system.cpu.exception_active[exception_number] = true;
+    Ok(())
+}
+
+// ExceptionReturn() of the ARM pseudocode
+pub fn exception_return(system: &mut M0System, exc_return: u32) -> u32 {
+    println!("\t EXCEPTION RETURN: {:08x}", exc_return);
+    let returning_exception_number: usize = (system.cpu.ipsr & 0x3f) as usize;
+    if exc_return & 0x0ffffff0 != 0x0ffffff0
+        || !system.cpu.exception_active[returning_exception_number]
+    {
+        return hard_fault("invalid EXC_RETURN", system);
+    }
+    let spsel: usize = match exc_return & 0xf {
+        0b0001 | 0b1001 => 0,
+        0b1101 => 1,
+        _ => return hard_fault("invalid EXC_RETURN", system),
+    };
+    system.cpu.exception_active[returning_exception_number] = false;
+    if let Some(n) = pending_exception(system) {
+        if exception_priority(system, n) < execution_priority(system) {
+            return tail_chain(system, n, exc_return);
+        }
+    }
+    let frame: [u32; 8] = match read_stack(system, spsel) {
+        Ok(frame) => frame,
+        Err(e) => return bus_error(e, system),
+    };
+    // the frame is checked before any of it is restored
+    let stacked_ipsr: usize = (frame[7] & 0x3f) as usize;
+    if stacked_ipsr >= EXC_MAX {
+        return hard_fault("invalid EXC_RETURN", system);
+    }
+    let to_handler: bool = exc_return & 0xf == 0b0001;
+    if to_handler != (stacked_ipsr != 0) {
+        return unpredicable(system);
+    }
+    system.cpu.ctrl_spsel = spsel;
+    pop_stack(system, spsel, &frame);
+    // the faults have been handled once the thread mode is resumed
+    if !to_handler {
+        system.fault_chain.clear();
+        system.fault_frame = None;
+    }
+    EXCEPTION_RETURN_CYCLES
+}
+
+// the pending exception is taken on the frame of the returning one without unstacking
+fn tail_chain(system: &mut M0System, exception_number: usize, exc_return: u32) -> u32 {
+    println!("\t TAIL CHAINING: {}", exception_number);
+    clear_exception_pending(system, exception_number);
+    if let Err(e) = exception_taken(system, exception_number) {
+        return entry_fault(e, system, exception_number);
+    }
+    system.cpu.lr = exc_return;
+    EXCEPTION_TAIL_CHAIN_CYCLES
+}
+
+fn read_stack(system: &mut M0System, spsel: usize) -> Result<[u32; 8], String> {
+    let frameptr: u32 = system.cpu.sp[spsel];
+    let mut frame: [u32; 8] = [0; 8];
+    for (i, val) in frame.iter_mut().enumerate() {
+        *val = system.read32(frameptr.wrapping_add(4 * i as u32))?;
+    }
+    Ok(frame)
+}
+
+// PopStack() of the ARM pseudocode
+fn pop_stack(system: &mut M0System, spsel: usize, frame: &[u32; 8]) {
+    let frameptr: u32 = system.cpu.sp[spsel];
+    system.cpu.r[..4].copy_from_slice(&frame[..4]);
+    system.cpu.r[12] = frame[4];
+    system.cpu.lr = frame[5];
+    system.cpu.pc = frame[6] & 0xfffffffe;
+    let psr: u32 = frame[7];
+    system.cpu.sp[spsel] = frameptr.wrapping_add(0x20) | (((psr >> 9) & 0b1) << 2);
+    system.cpu.apsr = (system.cpu.apsr & 0x0fffffff) | (psr & 0xf0000000);
+    system.cpu.ipsr = psr & 0x3f;
+    system.cpu.epsr = psr & EPSR_T;
+}
@@ -0,0 +1,84 @@
+use crate::exception::{EXC_IRQ0, EXC_NMI};
+use crate::nvic::IRQ_LINES;
+
+// an injected exception is raised when the cycle count reaches the value,
+// or before the instruction at the address is executed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Cycle(u64),
+    Pc(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Injection {
+    pub exception_number: usize,
+    pub trigger: Trigger,
+}
+
+impl Injection {
+    pub fn nmi(trigger: Trigger) -> Injection {
+        Injection {
+            exception_number: EXC_NMI,
+            trigger,
+        }
+    }
+
+    pub fn irq(irq: usize, trigger: Trigger) -> Injection {
+        Injection {
+            exception_number: EXC_IRQ0 + irq,
+            trigger,
+        }
+    }
+
+    // "<irq>@<cycle>", "<irq>@pc:<address>" or "nmi@..." as given to --irq
+    pub fn parse(spec: &str) -> Result<Injection, String> {
+        let (line, when) = spec
+            .split_once('@')
+            .ok_or(format!("Error: missing '@' in {}", spec))?;
+        let trigger: Trigger = parse_trigger(when)?;
+        if line == "nmi" {
+            return Ok(Injection::nmi(trigger));
+        }
+        match line.parse::<usize>() {
+            Ok(irq) if irq < IRQ_LINES => Ok(Injection::irq(irq, trigger)),
+            _ => Err(format!("Error: invalid IRQ number {}", line)),
+        }
+    }
+}
+
+fn parse_trigger(when: &str) -> Result<Trigger, String> {
+    if let Some(adrs) = when.strip_prefix("pc:") {
+        let digits: &str = adrs.trim_start_matches("0x");
+        return u32::from_str_radix(digits, 16)
+            .map(Trigger::Pc)
+            .map_err(|_| format!("Error: invalid address {}", adrs));
+    }
+    when.parse::<u64>()
+        .map(Trigger::Cycle)
+        .map_err(|_| format!("Error: invalid cycle {}", when))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_injection_parse() {
+        assert_eq!(
+            Injection::parse("5@100000"),
+            Ok(Injection::irq(5, Trigger::Cycle(100000)))
+        );
+        assert_eq!(
+            Injection::parse("31@pc:0x1f4"),
+            Ok(Injection::irq(31, Trigger::Pc(0x1f4)))
+        );
+        assert_eq!(
+            Injection::parse("nmi@pc:200"),
+            Ok(Injection::nmi(Trigger::Pc(0x200)))
+        );
+        assert!(Injection::parse("32@100").is_err());
+        assert!(Injection::parse("5").is_err());
+        assert!(Injection::parse("5@pc:xyz").is_err());
+        assert!(Injection::parse("5@-1").is_err());
+    }
+}
@@ -1,146 +1,431 @@
 use crate::bitdecode::*;
 use crate::cpu::M0System;
+use crate::cpu::Multiplier;
+use crate::cpu::EPSR_T;
 use crate::cpuflag::add_with_carry;
+use crate::cpuflag::shift_with_carry;
 use crate::cpuflag::ArmV6m;
-use crate::debug_info::{b16_fmt, b32_fmt};
-use crate::device::SystemMapAccess;
+use crate::cpuflag::CalcFlags;
+use crate::cpuflag::ShiftType;
+use crate::debug_info::b16_fmt;
+use crate::debug_info::reg_fmt;
+use crate::debug_info::sysm_fmt;
+use crate::exception::current_mode_is_handler;
+use crate::exception::exception_priority;
+use crate::exception::exception_return;
+use crate::exception::execution_priority;
+use crate::exception::hard_fault;
+use crate::exception::set_exception_pending;
+use crate::exception::EXC_SVCALL;
+use std::collections::HashMap;
 
 fn bit_count(bytecode: u32) -> u32 {
     let mut count = 0;
     for i in 0..32 {
-        if (bytecode & (1 << i)) == 1 {
+        if (bytecode & (1 << i)) != 0 {
             count += 1;
         }
     }
     count
 }
 
+// BXWritePC() of the ARM pseudocode: returns the cycles of the branch
+fn bx_write_pc(address: u32, system: &mut M0System) -> u32 {
+    if current_mode_is_handler(system) && address >> 28 == 0xf {
+        return exception_return(system, address);
+    }
+    blx_write_pc(address, system)
+}
+
+// BLXWritePC() of the ARM pseudocode: an EXC_RETURN value is not special
+fn blx_write_pc(address: u32, system: &mut M0System) -> u32 {
+    if address & 0b1 == 0 {
+        system.cpu.epsr &= !EPSR_T;
+    } else {
+        system.cpu.epsr |= EPSR_T;
+    }
+    system.cpu.pc = address & 0xfffffffe;
+    3
+}
+
+// R[n] of the ARM pseudocode: PC reads as the instruction address + 4
+fn read_register(regnum: usize, system: &M0System) -> u32 {
+    match regnum {
+        0..=12 => system.cpu.r[regnum],
+        13 => system.cpu.sp[system.cpu.ctrl_spsel],
+        14 => system.cpu.lr,
+        _ => system.cpu.pc + 4,
+    }
+}
+
+// R[n] = value for r0-r14; SP bits[1:0] are always zero
+fn write_register(regnum: usize, value: u32, system: &mut M0System) {
+    match regnum {
+        0..=12 => system.cpu.r[regnum] = value,
+        13 => system.cpu.sp[system.cpu.ctrl_spsel] = value & 0xfffffffc,
+        _ => system.cpu.lr = value,
+    }
+}
+
+// CurrentModeIsPrivileged() of the ARM pseudocode
+pub fn current_mode_is_privileged(system: &M0System) -> bool {
+    system.cpu.ipsr & 0x3f != 0 || system.cpu.ctrl_npriv == 0
+}
+
 // instructions (special)
 
-pub fn unpredicable(system: &mut M0System) -> u32 {
+pub fn unpredicable(_system: &mut M0System) -> u32 {
     println!("\t UNPREDICABLE ERROR");
     0
 }
 
-pub fn not_impremented(system: &mut M0System) -> u32 {
+pub fn not_impremented(_system: &mut M0System) -> u32 {
     println!("\t (not impremented)");
     0
 }
 
+pub fn bus_error(message: String, system: &mut M0System) -> u32 {
+    println!("\t BUS ERROR: {}", message);
+    hard_fault("bus error", system)
+}
+
+pub fn unaligned_access(adrs: u32, system: &mut M0System) -> u32 {
+    println!("\t UNALIGNED ACCESS: {:08x}", adrs);
+    hard_fault("unaligned access", system)
+}
+
+pub fn undefined_instruction(bytecode: u16, system: &mut M0System) -> u32 {
+    println!("\t (Undefined Instruction {:016b})", bytecode);
+    hard_fault("undefined instruction", system)
+}
+
+pub fn undefined_instruction_32(bytecode32: u32, system: &mut M0System) -> u32 {
+    println!("\t (Undefined Instruction (32bit) {:032b})", bytecode32);
+    hard_fault("undefined instruction", system)
+}
+
+pub fn invalid_state(system: &mut M0System) -> u32 {
+    println!("\t INVALID STATE: EPSR.T is 0 at {:08x}", system.cpu.pc);
+    hard_fault("invalid state", system)
+}
+
 // instructions: A
-// instructions: B
 
-pub fn b_16(bytecode: u16, system: &mut M0System) -> u32 {
-    // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.239
+pub fn adc_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ADC (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0101 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t adcs\tr{}, r{}", rdn, rm);
+    let carry: u32 = ArmV6m::new(system.cpu.apsr).c;
+    let r: ArmV6m = add_with_carry(system.cpu.r[rdn], system.cpu.r[rm], carry);
+    system.cpu.r[rdn] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn and_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf AND (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0000 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t ands\tr{}, r{}", rdn, rm);
+    let result: u32 = system.cpu.r[rdn] & system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn asr_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ASR (register)
     system.cpu.pc += 2;
-    if bytecode & (0b1 << 12) == 0b1 {
-        println!("conditional branch");
+    let field = parse_bit_u(&bytecode, "010000 0100 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t asrs\tr{}, r{}", rdn, rm);
+    let value: u32 = system.cpu.r[rdn];
+    let shift_n: u32 = system.cpu.r[rm] & 0xff;
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(value, ShiftType::Asr, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn asr_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ASR (immediate)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "000 10 iiiii mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    let shift_n: u32 = match field["i"] {
+        0 => 32,
+        imm5 => imm5 as u32,
+    };
+    println!("\t\t asrs\tr{}, r{}, #{}", rd, rm, shift_n);
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(system.cpu.r[rm], ShiftType::Asr, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rd] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn add_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ADD (immediate)
+    system.cpu.pc += 2;
+    let (rd, rn, imm32) = if bytecode >> 11 == 0b00110 {
+        let field = parse_bit_u(&bytecode, "001 10 ddd iiiiiiii").unwrap();
+        let rdn: usize = field["d"] as usize;
+        println!("\t\t adds\tr{}, #{}", rdn, field["i"]);
+        (rdn, rdn, field["i"] as u32)
+    } else {
+        let field = parse_bit_u(&bytecode, "000111 0 iii nnn ddd").unwrap();
+        let rd: usize = field["d"] as usize;
+        let rn: usize = field["n"] as usize;
+        println!("\t\t adds\tr{}, r{}, #{}", rd, rn, field["i"]);
+        (rd, rn, field["i"] as u32)
+    };
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], imm32, 0);
+    system.cpu.r[rd] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn add_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ADD (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "000110 0 mmm nnn ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t adds\tr{}, r{}, r{}", rd, rn, rm);
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], system.cpu.r[rm], 0);
+    system.cpu.r[rd] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn add_high_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ADD (register)
+    let field = parse_bit_u(&bytecode, "01000100 d mmmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t add\t{}, {}", reg_fmt(rdn), reg_fmt(rm));
+    if rdn == 15 && rm == 15 {
+        return unpredicable(system);
+    }
+    let result: u32 = read_register(rdn, system).wrapping_add(read_register(rm, system));
+    if rdn == 15 {
+        system.cpu.pc = result & 0xfffffffe;
+        3
+    } else {
+        write_register(rdn, result, system);
+        system.cpu.pc += 2;
+        1
     }
-    else {
+}
+
+// instructions: B
+
+pub fn b_16(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf B
+    let mnemonic: String;
+    let imm32: u32;
+    if bytecode & (0b1 << 12) != 0 {
+        // T1: 1101 cond[4] imm8[8]
+        let field = parse_bit_u(&bytecode, "1101 cccc iiiiiiii").unwrap();
+        let flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+        let (passed, cond_str) = flags.cond(field["c"] as u32);
+        imm32 = ((field["i"] as u8 as i8 as i32) << 1) as u32;
+        mnemonic = format!("b{}", cond_str);
+        if !passed {
+            println!("\t\t {}\t#{:+}\t\t; not taken", mnemonic, imm32 as i32);
+            system.cpu.pc += 2;
+            return 1;
+        }
+    } else {
+        // T2: 11100 imm11[11]
         let field = parse_bit_u(&bytecode, "11100 iiiiiiiiiii").unwrap();
         let imm11: u32 = field["i"] as u32;
-        let sign_flag: u32 = imm11 >> 10;
-        println!("s:{}", sign_flag);
-        let mut imm32: u32 = 0;
-        if sign_flag == 0b1 {
-            imm32 = 0xffffffff & !0b111111111111;
-            println!("minus imm32:{:08x}", imm32);
-            
-        }
-        imm32 |=  imm11 << 1;
-        println!("imm32:{:08x}", imm32);
-        let next_pc: u32 = system.cpu.pc.wrapping_add(imm32);
-        system.cpu.pc = next_pc;
-
-        println!("\t\t b\t#{:+}\t\t; {:08x}", imm32 as i32, next_pc);
+        imm32 = (((imm11 << 21) as i32) >> 20) as u32;
+        mnemonic = "b".to_string();
     }
-    1
+    let next_pc: u32 = system.cpu.pc.wrapping_add(4).wrapping_add(imm32);
+    println!(
+        "\t\t {}\t#{:+}\t\t; {:08x}",
+        mnemonic, imm32 as i32, next_pc
+    );
+    system.cpu.pc = next_pc;
+    3
 }
 
 pub fn b_32(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.239
     // Ref: Thumb-2SupplementReferencemanual.pdf p.122
-    system.cpu.pc += 4;
-    if bytecode32 & (0b1 << 12) == 0b1 {
-        let field = parse_bit_u(&bytecode32, "111110 S iiiiiiiiii 10 j 1 k aaaaaaaaaaa").unwrap();
-        let sign_flag: u32 = field["S"];
-        let i1: u32 = !(field["j"] ^ field["S"]) & 0b1;
-        let i2: u32 = !(field["k"] ^ field["S"]) & 0b1;
-        let imm10: u32 = field["i"];
-        let imm11: u32 = field["a"];
-        let mut imm32: u32 = 0;
-        if sign_flag == 0b1 {
-            imm32 = 0xffffffff & !0b111111111111111111111111;
-        }
-        imm32 |= i1 << 24 | i2 << 23 | imm10 << 12 | imm11 << 1;
-        let next_pc: u32 = system.cpu.pc.wrapping_add(imm32);
-        system.cpu.pc = next_pc;
-
-        println!("\t\t b\t#{:+}\t\t; {:08x}", imm32 as i32, next_pc);
+    if bytecode32 & (0b1 << 12) == 0 {
+        // T3 (conditional) is not a part of ARMv6-M
+        return undefined_instruction_32(bytecode32, system);
     }
-    else {
-        println!("conditional branch");
-    }
-    1
+    let field = parse_bit_u(&bytecode32, "11110 S iiiiiiiiii 10 j 1 k aaaaaaaaaaa").unwrap();
+    let imm32: u32 = branch_offset_32(&field);
+    let next_pc: u32 = system.cpu.pc.wrapping_add(4).wrapping_add(imm32);
+    println!("\t\t b.w\t#{:+}\t\t; {:08x}", imm32 as i32, next_pc);
+    system.cpu.pc = next_pc;
+    3
 }
 
 pub fn bl_32(bytecode32: u32, system: &mut M0System) -> u32 {
-    // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.248
-    system.cpu.pc += 4;
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf BL
     let field = parse_bit_u(&bytecode32, "11110 S iiiiiiiiii 11 j 1 k aaaaaaaaaaa").unwrap();
-    let sign_flag: u32 = field["S"];
-    let i1: u32 = !(field["j"] ^ field["S"]) & 0b1;
-    let i2: u32 = !(field["k"] ^ field["S"]) & 0b1;
-    let imm10: u32 = field["i"];
-    let imm11: u32 = field["a"];
-    let mut imm32: u32 = 0;
-    if sign_flag == 0b1 {
-        imm32 = 0xffffffff & 0b000000000000000000000000;
-    }
-    imm32 |= i1 << 24 | i2 << 23 | imm10 << 12 | imm11 << 1;
-    let next_pc: u32 = system.cpu.pc.wrapping_add(imm32);
-
-    system.cpu.lr = (system.cpu.pc & 0xfffffffe) | 0b1;
+    let imm32: u32 = branch_offset_32(&field);
+    let next_instr_addr: u32 = system.cpu.pc.wrapping_add(4);
+    let next_pc: u32 = next_instr_addr.wrapping_add(imm32);
+    println!("\t\t bl\t#{:+}\t\t; {:08x}", imm32 as i32, next_pc);
+    system.cpu.lr = next_instr_addr | 0b1;
     system.cpu.pc = next_pc;
+    4
+}
 
-    println!("\t\t bl\t#{:+}\t\t; {:08x}", imm32 as i32, next_pc);
-    1
+// SignExtend(S:I1:I2:imm10:imm11:'0', 32) with I1 = NOT(J1 EOR S), I2 = NOT(J2 EOR S)
+fn branch_offset_32(field: &HashMap<String, u32>) -> u32 {
+    let s: u32 = field["S"];
+    let i1: u32 = !(field["j"] ^ s) & 0b1;
+    let i2: u32 = !(field["k"] ^ s) & 0b1;
+    let imm25: u32 = s << 24 | i1 << 23 | i2 << 22 | field["i"] << 12 | field["a"] << 1;
+    (((imm25 << 7) as i32) >> 7) as u32
+}
+
+pub fn blx(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf BLX (register)
+    let field = parse_bit_u(&bytecode, "010001 11 1 mmmm").unwrap();
+    let rm: usize = field["m"] as usize;
+    println!("\t\t blx\t{}", reg_fmt(rm));
+    if rm == 15 {
+        return unpredicable(system);
+    }
+    let target: u32 = read_register(rm, system);
+    system.cpu.lr = (system.cpu.pc + 2) | 0b1;
+    blx_write_pc(target, system)
 }
 
 pub fn bkpt(bytecode: u16, system: &mut M0System) -> u32 {
-    // Ref: Thumb-2SupplementReferencemanual.pdf p.132
-    println!("\t bkpt");
-    not_impremented(system)
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf BKPT
+    let field = parse_bit_u(&bytecode, "10111110 iiiiiiii").unwrap();
+    println!("\t\t bkpt\t#{}", field["i"]);
+    // without a debugger the breakpoint escalates to HardFault
+    hard_fault("breakpoint", system)
 }
 
 pub fn bx(bytecode: u16, system: &mut M0System) -> u32 {
-    system.cpu.pc += 2;
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf BX
     let field = parse_bit_u(&bytecode, "010001 11 0 mmmm").unwrap();
     let rm: usize = field["m"] as usize;
-
-    match rm {
-        15 => (),
-        14 => {
-            println!("\t\t bx\tlr");
-            system.cpu.pc = system.cpu.lr & 0xfffffffe;
-        },
-        _ => {
-            println!("\t\t bx\tr{}", rm);
-            system.cpu.pc = system.cpu.r[rm] & 0xfffffffe;
-        },
+    println!("\t\t bx\t{}", reg_fmt(rm));
+    if rm == 15 {
+        return unpredicable(system);
     }
+    system.cpu.pc += 2;
+    bx_write_pc(read_register(rm, system), system)
+}
+
+pub fn bic_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf BIC (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1110 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t bics\tr{}, r{}", rdn, rm);
+    let result: u32 = system.cpu.r[rdn] & !system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
     1
 }
+
 // instructions: C
 
 pub fn cbz(bytecode: u16, system: &mut M0System) -> u32 {
+    // not a part of ARMv6-M
     println!("\t\t cbz");
-    not_impremented(system)
+    undefined_instruction(bytecode, system)
 }
 
 pub fn cbnz(bytecode: u16, system: &mut M0System) -> u32 {
+    // not a part of ARMv6-M
     println!("\t\t cbnz");
-    not_impremented(system)
+    undefined_instruction(bytecode, system)
+}
+
+pub fn cmn_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf CMN (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1011 mmm nnn").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    println!("\t\t cmn\tr{}, r{}", rn, rm);
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], system.cpu.r[rm], 0);
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn cmp_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf CMP (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1010 mmm nnn").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    println!("\t\t cmp\tr{}, r{}", rn, rm);
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], !system.cpu.r[rm], 1);
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn cmp_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf CMP (immediate)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "001 01 nnn iiiiiiii").unwrap();
+    let rn: usize = field["n"] as usize;
+    let imm32: u32 = field["i"] as u32;
+    println!("\t\t cmp\tr{}, #{}", rn, imm32);
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], !imm32, 1);
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn cmp_high_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf CMP (register)
+    let field = parse_bit_u(&bytecode, "01000101 n mmmm nnn").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    println!("\t\t cmp\t{}, {}", reg_fmt(rn), reg_fmt(rm));
+    if (rn < 8 && rm < 8) || rn == 15 || rm == 15 {
+        return unpredicable(system);
+    }
+    let r: ArmV6m = add_with_carry(read_register(rn, system), !read_register(rm, system), 1);
+    system.cpu.apsr = r.flags_to_apsr();
+    system.cpu.pc += 2;
+    1
+}
+
+pub fn cps(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf CPS
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10110110011 d 0010").unwrap();
+    let disable: usize = field["d"] as usize;
+    println!("\t\t cps{}\ti", if disable == 1 { "id" } else { "ie" });
+    if current_mode_is_privileged(system) {
+        system.cpu.primask_pm = disable;
+    }
+    1
 }
 
 // instructions: C
@@ -148,52 +433,504 @@ pub fn cbnz(bytecode: u16, system: &mut M0System) -> u32 {
 
 pub fn dbg(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.164
-    println!("\t dpg");
-    not_impremented(system)
+    // not a part of ARMv6-M
+    println!("\t dbg");
+    undefined_instruction(bytecode, system)
+}
+
+pub fn dmb_32(_bytecode32: u32, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf DMB
+    // memory accesses complete in order, so a barrier has nothing to wait for
+    println!("\t\t dmb\tsy");
+    system.cpu.pc += 4;
+    4
+}
+
+pub fn dsb_32(_bytecode32: u32, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf DSB
+    println!("\t\t dsb\tsy");
+    system.cpu.pc += 4;
+    4
 }
 
 // instructions: E
+
+pub fn eor_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf EOR (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0001 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t eors\tr{}, r{}", rdn, rm);
+    let result: u32 = system.cpu.r[rdn] ^ system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
 // instructions: F
 // instructions: G
 // instructions: H
 
 pub fn hint_32(bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.170
-    not_impremented(system)
+    // not a part of ARMv6-M
+    undefined_instruction_32(bytecode32, system)
 }
 
 // instructions: I
 
 pub fn it(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.176
+    // not a part of ARMv6-M
     println!("\t it");
-    not_impremented(system)
+    undefined_instruction(bytecode, system)
+}
+
+pub fn isb_32(_bytecode32: u32, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ISB
+    println!("\t\t isb\tsy");
+    system.cpu.pc += 4;
+    4
 }
 
 // instructions: J
 // instructions: L
+
+pub fn lsl_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LSL (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0010 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t lsls\tr{}, r{}", rdn, rm);
+    let value: u32 = system.cpu.r[rdn];
+    let shift_n: u32 = system.cpu.r[rm] & 0xff;
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(value, ShiftType::Lsl, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn lsr_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LSR (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0011 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t lsrs\tr{}, r{}", rdn, rm);
+    let value: u32 = system.cpu.r[rdn];
+    let shift_n: u32 = system.cpu.r[rm] & 0xff;
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(value, ShiftType::Lsr, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn lsl_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LSL (immediate)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "000 00 iiiii mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    let shift_n: u32 = field["i"] as u32;
+    println!("\t\t lsls\tr{}, r{}, #{}", rd, rm, shift_n);
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(system.cpu.r[rm], ShiftType::Lsl, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rd] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn lsr_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LSR (immediate)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "000 01 iiiii mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    let shift_n: u32 = match field["i"] {
+        0 => 32,
+        imm5 => imm5 as u32,
+    };
+    println!("\t\t lsrs\tr{}, r{}, #{}", rd, rm, shift_n);
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(system.cpu.r[rm], ShiftType::Lsr, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rd] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn ldr_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDR (immediate)
+    let field = parse_bit_u(&bytecode, "01101 iiiii nnn ttt").unwrap();
+    let imm32: u32 = (field["i"] as u32) << 2;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(imm32);
+    println!(
+        "\t\t ldr\tr{}, [r{}, #{}]\t; {:08x}",
+        rt, rn, imm32, address
+    );
+    if address & 0b11 != 0 {
+        return unaligned_access(address, system);
+    }
+    match system.read32(address) {
+        Ok(data) => system.cpu.r[rt] = data,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldrb_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDRB (immediate)
+    let field = parse_bit_u(&bytecode, "01111 iiiii nnn ttt").unwrap();
+    let imm32: u32 = field["i"] as u32;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(imm32);
+    println!(
+        "\t\t ldrb\tr{}, [r{}, #{}]\t; {:08x}",
+        rt, rn, imm32, address
+    );
+    match system.read8(address) {
+        Ok(data) => system.cpu.r[rt] = data as u32,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldrh_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDRH (immediate)
+    let field = parse_bit_u(&bytecode, "10001 iiiii nnn ttt").unwrap();
+    let imm32: u32 = (field["i"] as u32) << 1;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(imm32);
+    println!(
+        "\t\t ldrh\tr{}, [r{}, #{}]\t; {:08x}",
+        rt, rn, imm32, address
+    );
+    if address & 0b1 != 0 {
+        return unaligned_access(address, system);
+    }
+    match system.read16(address) {
+        Ok(data) => system.cpu.r[rt] = data as u32,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldr_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDR (register)
+    let field = parse_bit_u(&bytecode, "0101 100 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t ldr\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    if address & 0b11 != 0 {
+        return unaligned_access(address, system);
+    }
+    match system.read32(address) {
+        Ok(data) => system.cpu.r[rt] = data,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldrb_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDRB (register)
+    let field = parse_bit_u(&bytecode, "0101 110 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t ldrb\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    match system.read8(address) {
+        Ok(data) => system.cpu.r[rt] = data as u32,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldrh_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDRH (register)
+    let field = parse_bit_u(&bytecode, "0101 101 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t ldrh\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    if address & 0b1 != 0 {
+        return unaligned_access(address, system);
+    }
+    match system.read16(address) {
+        Ok(data) => system.cpu.r[rt] = data as u32,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldrsb(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDRSB
+    let field = parse_bit_u(&bytecode, "0101 011 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t ldrsb\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    match system.read8(address) {
+        Ok(data) => system.cpu.r[rt] = data as i8 as u32,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldrsh(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDRSH
+    let field = parse_bit_u(&bytecode, "0101 111 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t ldrsh\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    if address & 0b1 != 0 {
+        return unaligned_access(address, system);
+    }
+    match system.read16(address) {
+        Ok(data) => system.cpu.r[rt] = data as i16 as u32,
+        Err(e) => return bus_error(e, system),
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn ldm(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf LDM, LDMIA, LDMFD
+    let field = parse_bit_u(&bytecode, "11001 nnn rrrrrrrr").unwrap();
+    let rn: usize = field["n"] as usize;
+    let reglist: u16 = field["r"];
+    let wback: bool = (reglist & (1 << rn)) == 0;
+    println!(
+        "\t\t ldm\tr{}{}, {}",
+        rn,
+        if wback { "!" } else { "" },
+        b16_fmt(reglist)
+    );
+    if reglist == 0 {
+        return unpredicable(system);
+    }
+    let mut address: u32 = system.cpu.r[rn];
+    if address & 0b11 != 0 {
+        return unaligned_access(address, system);
+    }
+    let mut data: [u32; 8] = [0; 8];
+    for (i, val) in data.iter_mut().enumerate() {
+        if (reglist & (1 << i)) != 0 {
+            match system.read32(address) {
+                Ok(d) => *val = d,
+                Err(e) => return bus_error(e, system),
+            }
+            address = address.wrapping_add(4);
+        }
+    }
+    for (i, val) in data.iter().enumerate() {
+        if (reglist & (1 << i)) != 0 {
+            system.cpu.r[i] = *val;
+        }
+    }
+    if wback {
+        system.cpu.r[rn] = address;
+    }
+    system.cpu.pc += 2;
+    1 + bit_count(reglist as u32)
+}
+
 // instructions: M
 
 pub fn mrs_32(bytecode32: u32, system: &mut M0System) -> u32 {
-    // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.357
-    not_impremented(system)
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MRS
+    let field = parse_bit_u(&bytecode32, "11110 0 1111 1 0 1111 10 0 0 dddd ssssssss").unwrap();
+    let rd: usize = field["d"] as usize;
+    let sysm: u32 = field["s"];
+    println!("\t\t mrs\t{}, {}", reg_fmt(rd), sysm_fmt(sysm));
+    if rd == 13 || rd == 15 {
+        return unpredicable(system);
+    }
+    let mut value: u32 = 0;
+    match sysm >> 3 {
+        0b00000 => {
+            if sysm & 0b001 != 0 {
+                value |= system.cpu.ipsr & 0x1ff;
+            }
+            // EPSR reads as zero
+            if sysm & 0b100 == 0 {
+                value |= system.cpu.apsr & 0xf0000000;
+            }
+        }
+        0b00001 if current_mode_is_privileged(system) => match sysm & 0b111 {
+            0b000 => value = system.cpu.sp[0],
+            0b001 => value = system.cpu.sp[1],
+            _ => (),
+        },
+        0b00010 => match sysm & 0b111 {
+            0b000 => value = system.cpu.primask_pm as u32,
+            0b100 => value = ((system.cpu.ctrl_spsel << 1) | system.cpu.ctrl_npriv) as u32,
+            _ => (),
+        },
+        _ => (),
+    }
+    write_register(rd, value, system);
+    system.cpu.pc += 4;
+    3
 }
 
 pub fn msr_32(bytecode32: u32, system: &mut M0System) -> u32 {
-    // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.358
-    not_impremented(system)
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MSR (register)
+    let field = parse_bit_u(&bytecode32, "11110 0 1110 0 0 nnnn 10 0 0 1000 ssssssss").unwrap();
+    let rn: usize = field["n"] as usize;
+    let sysm: u32 = field["s"];
+    println!("\t\t msr\t{}, {}", sysm_fmt(sysm), reg_fmt(rn));
+    if rn == 13 || rn == 15 {
+        return unpredicable(system);
+    }
+    let value: u32 = system.cpu.r[rn];
+    let privileged: bool = current_mode_is_privileged(system);
+    match sysm >> 3 {
+        // IPSR and EPSR are read only
+        0b00000 if sysm & 0b100 == 0 => {
+            system.cpu.apsr = (system.cpu.apsr & 0x0fffffff) | (value & 0xf0000000);
+        }
+        0b00001 if privileged => match sysm & 0b111 {
+            0b000 => system.cpu.sp[0] = value & 0xfffffffc,
+            0b001 => system.cpu.sp[1] = value & 0xfffffffc,
+            _ => (),
+        },
+        0b00010 => match sysm & 0b111 {
+            0b000 if privileged => system.cpu.primask_pm = (value & 0b1) as usize,
+            0b100 if privileged => {
+                system.cpu.ctrl_npriv = (value & 0b1) as usize;
+                // SPSEL can only be changed in thread mode
+                if system.cpu.ipsr & 0x3f == 0 {
+                    system.cpu.ctrl_spsel = ((value >> 1) & 0b1) as usize;
+                }
+            }
+            _ => (),
+        },
+        _ => (),
+    }
+    system.cpu.pc += 4;
+    3
+}
+
+pub fn mul(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MUL
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1101 nnn ddd").unwrap();
+    let rn: usize = field["n"] as usize;
+    let rdm: usize = field["d"] as usize;
+    println!("\t\t muls\tr{}, r{}, r{}", rdm, rn, rdm);
+    let result: u32 = system.cpu.r[rn].wrapping_mul(system.cpu.r[rdm]);
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rdm] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    match system.multiplier {
+        Multiplier::Fast => 1,
+        Multiplier::Small => 32,
+    }
+}
+
+pub fn mvn_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MVN (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1111 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t mvns\tr{}, r{}", rd, rm);
+    let result: u32 = !system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rd] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn mov_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MOV (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "000 00 00000 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t movs\tr{}, r{}", rd, rm);
+    let result: u32 = system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rd] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn mov_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MOV (immediate)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "001 00 ddd iiiiiiii").unwrap();
+    let rd: usize = field["d"] as usize;
+    let imm32: u32 = field["i"] as u32;
+    println!("\t\t movs\tr{}, #{}", rd, imm32);
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(imm32);
+    system.cpu.r[rd] = imm32;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn mov_high_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf MOV (register)
+    let field = parse_bit_u(&bytecode, "01000110 d mmmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t mov\t{}, {}", reg_fmt(rd), reg_fmt(rm));
+    let result: u32 = read_register(rm, system);
+    if rd == 15 {
+        system.cpu.pc = result & 0xfffffffe;
+        3
+    } else {
+        write_register(rd, result, system);
+        system.cpu.pc += 2;
+        1
+    }
 }
 
 // instructions: N
 
-pub fn nop(bytecode: u16, system: &mut M0System) -> u32 {
+pub fn nop(_bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.273
     println!("\t nop");
     system.cpu.pc += 2;
     1
 }
 
-pub fn nop_32(bytecode32: u32, system: &mut M0System) -> u32 {
+pub fn nop_32(_bytecode32: u32, system: &mut M0System) -> u32 {
     // Ref:DDI0403D_arm_architecture_v7m_reference_manual.pdf p.366
     println!("\t nop (32bit)");
     system.cpu.pc += 4;
@@ -201,72 +938,481 @@ pub fn nop_32(bytecode32: u32, system: &mut M0System) -> u32 {
 }
 
 // instructions: O
+
+pub fn orr_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ORR (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1100 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t orrs\tr{}, r{}", rdn, rm);
+    let result: u32 = system.cpu.r[rdn] | system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
 // instructions: P
 
 pub fn pop(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.293
-    println!("\t pop");
-    not_impremented(system)
+    let field = parse_bit_u(&bytecode, "1011 110 p rrrrrrrr").unwrap();
+    let reglist: u16 = field["r"];
+    let pc_flag: u16 = field["p"];
+    println!("\t pop reglist:{} pc:{}", b16_fmt(reglist), pc_flag);
+    let registers: u32 = ((pc_flag as u32) << 15) | reglist as u32;
+    if registers == 0 {
+        return unpredicable(system);
+    }
+    let original_sp: u32 = system.cpu.sp[system.cpu.ctrl_spsel];
+    let mut current_sp: u32 = original_sp;
+    let mut data: [u32; 8] = [0; 8];
+    for (i, val) in data.iter_mut().enumerate() {
+        if (reglist & (1 << i)) != 0 {
+            println!("\t\t pop r{} from {:08x}", i, current_sp);
+            match system.read32(current_sp) {
+                Ok(d) => *val = d,
+                Err(e) => return bus_error(e, system),
+            }
+            current_sp = current_sp.wrapping_add(4);
+        }
+    }
+    let mut new_pc: u32 = 0;
+    if pc_flag == 1 {
+        println!("\t\t pop pc from {:08x}", current_sp);
+        match system.read32(current_sp) {
+            Ok(d) => new_pc = d,
+            Err(e) => return bus_error(e, system),
+        }
+    }
+    for (i, val) in data.iter().enumerate() {
+        if (reglist & (1 << i)) != 0 {
+            system.cpu.r[i] = *val;
+        }
+    }
+    system.cpu.sp[system.cpu.ctrl_spsel] = original_sp.wrapping_add(4 * bit_count(registers));
+    if pc_flag == 1 {
+        match bx_write_pc(new_pc, system) {
+            0 => 0,
+            cycles => cycles + bit_count(registers),
+        }
+    } else {
+        system.cpu.pc += 2;
+        1 + bit_count(registers)
+    }
 }
 
 pub fn push(bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.295
-    system.cpu.pc += 2;
-    let reglist: u16 = (bytecode & 0xff) as u16;
-    println!("\t push reglist:{}", b16_fmt(reglist));
-    if reglist == 0 {
-        unpredicable(system)
-    } else {
-        let original_sp: u32 = system.cpu.sp[system.cpu.ctrl_spsel];
-        let mut current_sp: u32 = system.cpu.sp[system.cpu.ctrl_spsel] - bit_count(reglist as u32);
-        system.cpu.sp[system.cpu.ctrl_spsel] = current_sp;
-        for i in 0..13 {
-            if (reglist & (1 << i)) != 0 {
-                println!("\t\t push r{} to {:08x}", i, current_sp);
-                system.system_map.write32(current_sp, system.cpu.r[i]);
-                current_sp += 4;
+    let field = parse_bit_u(&bytecode, "1011 010 m rrrrrrrr").unwrap();
+    let reglist: u16 = field["r"];
+    let lr_flag: u16 = field["m"];
+    println!("\t push reglist:{} lr:{}", b16_fmt(reglist), lr_flag);
+    let registers: u32 = ((lr_flag as u32) << 14) | reglist as u32;
+    if registers == 0 {
+        return unpredicable(system);
+    }
+    let new_sp: u32 = system.cpu.sp[system.cpu.ctrl_spsel].wrapping_sub(4 * bit_count(registers));
+    let mut current_sp: u32 = new_sp;
+    for i in 0..8 {
+        if (reglist & (1 << i)) != 0 {
+            println!("\t\t push r{} to {:08x}", i, current_sp);
+            if let Err(e) = system.write32(current_sp, system.cpu.r[i]) {
+                return bus_error(e, system);
             }
+            current_sp = current_sp.wrapping_add(4);
+        }
+    }
+    if lr_flag == 1 {
+        println!("\t\t push lr to {:08x}", current_sp);
+        if let Err(e) = system.write32(current_sp, system.cpu.lr) {
+            return bus_error(e, system);
         }
-        assert_eq!(system.cpu.sp[system.cpu.ctrl_spsel], original_sp);
-
-        1
     }
+    system.cpu.sp[system.cpu.ctrl_spsel] = new_sp;
+    system.cpu.pc += 2;
+    1 + bit_count(registers)
 }
 
 // instructions: Q
 // instructions: R
+
+pub fn ror_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf ROR (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0111 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t rors\tr{}, r{}", rdn, rm);
+    let value: u32 = system.cpu.r[rdn];
+    let shift_n: u32 = system.cpu.r[rm] & 0xff;
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    let (result, carry) = shift_with_carry(value, ShiftType::Ror, shift_n, flags.c);
+    flags.c = carry;
+    flags.set_nz(result);
+    system.cpu.r[rdn] = result;
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
+pub fn rsb_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf RSB (immediate)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1001 nnn ddd").unwrap();
+    let rn: usize = field["n"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t rsbs\tr{}, r{}, #0", rd, rn);
+    let r: ArmV6m = add_with_carry(!system.cpu.r[rn], 0, 1);
+    system.cpu.r[rd] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn rev(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf REV
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10111010 00 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t rev\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = value.swap_bytes();
+    1
+}
+
+pub fn rev16(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf REV16
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10111010 01 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t rev16\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = ((value & 0xff00ff00) >> 8) | ((value & 0x00ff00ff) << 8);
+    1
+}
+
+pub fn revsh(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf REVSH
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10111010 11 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t revsh\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = (value as u16).swap_bytes() as i16 as i32 as u32;
+    1
+}
+
 // instructions: S
 
-pub fn sev(bytecode: u16, system: &mut M0System) -> u32 {
+pub fn sev(_bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.596
     println!("\t sev");
-    not_impremented(system)
+    system.cpu.pc += 2;
+    1
+}
+
+pub fn sbc_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf SBC (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 0110 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rdn: usize = field["d"] as usize;
+    println!("\t\t sbcs\tr{}, r{}", rdn, rm);
+    let carry: u32 = ArmV6m::new(system.cpu.apsr).c;
+    let r: ArmV6m = add_with_carry(system.cpu.r[rdn], !system.cpu.r[rm], carry);
+    system.cpu.r[rdn] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn sub_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf SUB (immediate)
+    system.cpu.pc += 2;
+    let (rd, rn, imm32) = if bytecode >> 11 == 0b00111 {
+        let field = parse_bit_u(&bytecode, "001 11 ddd iiiiiiii").unwrap();
+        let rdn: usize = field["d"] as usize;
+        println!("\t\t subs\tr{}, #{}", rdn, field["i"]);
+        (rdn, rdn, field["i"] as u32)
+    } else {
+        let field = parse_bit_u(&bytecode, "000111 1 iii nnn ddd").unwrap();
+        let rd: usize = field["d"] as usize;
+        let rn: usize = field["n"] as usize;
+        println!("\t\t subs\tr{}, r{}, #{}", rd, rn, field["i"]);
+        (rd, rn, field["i"] as u32)
+    };
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], !imm32, 1);
+    system.cpu.r[rd] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn sub_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf SUB (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "000110 1 mmm nnn ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t subs\tr{}, r{}, r{}", rd, rn, rm);
+    let r: ArmV6m = add_with_carry(system.cpu.r[rn], !system.cpu.r[rm], 1);
+    system.cpu.r[rd] = r.result;
+    system.cpu.apsr = r.flags_to_apsr();
+    1
+}
+
+pub fn str_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STR (immediate)
+    let field = parse_bit_u(&bytecode, "01100 iiiii nnn ttt").unwrap();
+    let imm32: u32 = (field["i"] as u32) << 2;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(imm32);
+    println!(
+        "\t\t str\tr{}, [r{}, #{}]\t; {:08x}",
+        rt, rn, imm32, address
+    );
+    if address & 0b11 != 0 {
+        return unaligned_access(address, system);
+    }
+    if let Err(e) = system.write32(address, system.cpu.r[rt]) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn strb_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STRB (immediate)
+    let field = parse_bit_u(&bytecode, "01110 iiiii nnn ttt").unwrap();
+    let imm32: u32 = field["i"] as u32;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(imm32);
+    println!(
+        "\t\t strb\tr{}, [r{}, #{}]\t; {:08x}",
+        rt, rn, imm32, address
+    );
+    if let Err(e) = system.write8(address, system.cpu.r[rt] as u8) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn strh_immediate(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STRH (immediate)
+    let field = parse_bit_u(&bytecode, "10000 iiiii nnn ttt").unwrap();
+    let imm32: u32 = (field["i"] as u32) << 1;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(imm32);
+    println!(
+        "\t\t strh\tr{}, [r{}, #{}]\t; {:08x}",
+        rt, rn, imm32, address
+    );
+    if address & 0b1 != 0 {
+        return unaligned_access(address, system);
+    }
+    if let Err(e) = system.write16(address, system.cpu.r[rt] as u16) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn str_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STR (register)
+    let field = parse_bit_u(&bytecode, "0101 000 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t str\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    if address & 0b11 != 0 {
+        return unaligned_access(address, system);
+    }
+    if let Err(e) = system.write32(address, system.cpu.r[rt]) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn strb_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STRB (register)
+    let field = parse_bit_u(&bytecode, "0101 010 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t strb\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    if let Err(e) = system.write8(address, system.cpu.r[rt] as u8) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn strh_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STRH (register)
+    let field = parse_bit_u(&bytecode, "0101 001 mmm nnn ttt").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    let rt: usize = field["t"] as usize;
+    let address: u32 = system.cpu.r[rn].wrapping_add(system.cpu.r[rm]);
+    println!("\t\t strh\tr{}, [r{}, r{}]\t; {:08x}", rt, rn, rm, address);
+    if address & 0b1 != 0 {
+        return unaligned_access(address, system);
+    }
+    if let Err(e) = system.write16(address, system.cpu.r[rt] as u16) {
+        return bus_error(e, system);
+    }
+    system.cpu.pc += 2;
+    2
+}
+
+pub fn stm(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf STM, STMIA, STMEA
+    let field = parse_bit_u(&bytecode, "11000 nnn rrrrrrrr").unwrap();
+    let rn: usize = field["n"] as usize;
+    let reglist: u16 = field["r"];
+    println!("\t\t stm\tr{}!, {}", rn, b16_fmt(reglist));
+    if reglist == 0 {
+        return unpredicable(system);
+    }
+    // Rn in the list is only defined when it is the lowest register stored
+    let lowest: u16 = reglist & reglist.wrapping_neg();
+    if (reglist & (1 << rn)) != 0 && lowest != (1 << rn) {
+        return unpredicable(system);
+    }
+    let mut address: u32 = system.cpu.r[rn];
+    if address & 0b11 != 0 {
+        return unaligned_access(address, system);
+    }
+    for i in 0..8 {
+        if (reglist & (1 << i)) != 0 {
+            if let Err(e) = system.write32(address, system.cpu.r[i]) {
+                return bus_error(e, system);
+            }
+            address = address.wrapping_add(4);
+        }
+    }
+    system.cpu.r[rn] = address;
+    system.cpu.pc += 2;
+    1 + bit_count(reglist as u32)
+}
+
+pub fn svc(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf SVC
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "11011111 iiiiiiii").unwrap();
+    let imm8: u32 = field["i"] as u32;
+    println!("\t\t svc\t#{}", imm8);
+    // the handler finds the immediate at the stacked return address - 2
+    if exception_priority(system, EXC_SVCALL) >= execution_priority(system) {
+        return hard_fault("SVC at an insufficient priority", system);
+    }
+    set_exception_pending(system, EXC_SVCALL);
+    1
+}
+
+pub fn sxtb(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf SXTB
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10110010 01 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t sxtb\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = value as u8 as i8 as i32 as u32;
+    1
+}
+
+pub fn sxth(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf SXTH
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10110010 00 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t sxth\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = value as u16 as i16 as i32 as u32;
+    1
 }
 
 // instructions: T
+
+pub fn tst_register(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf TST (register)
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "010000 1000 mmm nnn").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rn: usize = field["n"] as usize;
+    println!("\t\t tst\tr{}, r{}", rn, rm);
+    let result: u32 = system.cpu.r[rn] & system.cpu.r[rm];
+    let mut flags: ArmV6m = ArmV6m::new(system.cpu.apsr);
+    flags.set_nz(result);
+    system.cpu.apsr = flags.flags_to_apsr();
+    1
+}
+
 // instructions: U
+
+pub fn uxtb(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf UXTB
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10110010 11 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t uxtb\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = value & 0xff;
+    1
+}
+
+pub fn uxth(bytecode: u16, system: &mut M0System) -> u32 {
+    // Ref: DDI0419C_arm_architecture_v6m_reference_manual.pdf UXTH
+    system.cpu.pc += 2;
+    let field = parse_bit_u(&bytecode, "10110010 10 mmm ddd").unwrap();
+    let rm: usize = field["m"] as usize;
+    let rd: usize = field["d"] as usize;
+    println!("\t\t uxth\tr{}, r{}", rd, rm);
+    let value: u32 = system.cpu.r[rm];
+    system.cpu.r[rd] = value & 0xffff;
+    1
+}
+
 // instructions: V
 // instructions: W
 
-pub fn wfe(bytecode: u16, system: &mut M0System) -> u32 {
+pub fn wfe(_bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.610
+    // the event register is not modelled, WFE never waits
     println!("\t wfe");
-    not_impremented(system)
+    system.cpu.pc += 2;
+    2
 }
 
-pub fn wfi(bytecode: u16, system: &mut M0System) -> u32 {
+pub fn wfi(_bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.612
+    // the processor does not sleep, a pending interrupt is taken by the next execute
     println!("\t wfi");
-    not_impremented(system)
+    system.cpu.pc += 2;
+    2
 }
 
 // instructions: X
 // instructions: Y
 
-pub fn cpu_yield(bytecode: u16, system: &mut M0System) -> u32 {
+pub fn cpu_yield(_bytecode: u16, system: &mut M0System) -> u32 {
     // Ref: Thumb-2SupplementReferencemanual.pdf p.614
     println!("\t yield");
-    not_impremented(system)
+    system.cpu.pc += 2;
+    1
 }
 
 // instructions: Z
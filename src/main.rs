@@ -3,16 +3,11 @@ use std::fs::File;
 use std::io::Read;
 use std::process::exit;
 
-#[macro_use]
-mod bitdecode;
-mod cpu;
-mod cpuflag;
-mod debug_info;
-mod device;
-mod instruction;
-
-use crate::cpu::SystemCtrl;
-use crate::device::SystemMapAccess;
+use corsim0::cpu;
+use corsim0::cpu::SystemCtrl;
+use corsim0::device;
+use corsim0::device::SystemMapAccess;
+use corsim0::injection;
 
 const ROMADDR: u32 = 0x00000000;
 const ROMSIZE: usize = 128 * 1024;
@@ -23,12 +18,33 @@ const RAMSIZE: usize = 128 * 1024;
 fn main() {
     let args: Vec<String> = env::args().collect::<Vec<String>>();
 
-    if args.len() < 2 {
-        println!("Usage: corsim0 image-file");
-        exit(1);
+    let mut filename: String = String::new();
+    let mut multiplier: cpu::Multiplier = cpu::Multiplier::Fast;
+    let mut fault_action: cpu::FaultAction = cpu::FaultAction::HardFault;
+    let mut injections: Vec<injection::Injection> = Vec::new();
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--small-multiplier" => multiplier = cpu::Multiplier::Small,
+            "--stop-on-fault" => fault_action = cpu::FaultAction::Stop,
+            "--irq" => {
+                let spec: &str = args_iter.next().map_or("", |spec| spec.as_str());
+                match injection::Injection::parse(spec) {
+                    Ok(injection) => injections.push(injection),
+                    Err(e) => {
+                        println!("{}", e);
+                        exit(1);
+                    }
+                }
+            }
+            _ => filename = arg.clone(),
+        }
     }
 
-    let filename: String = args[1].clone();
+    if filename.is_empty() {
+        println!("Usage: corsim0 [--small-multiplier] [--stop-on-fault] [--irq (<irq>|nmi)@(<cycle>|pc:<address>)]... image-file");
+        exit(1);
+    }
 
     let mut rom: device::MemoryMappedDevice = device::MemoryMappedDevice {
         name: "ROM".to_string(),
@@ -52,7 +68,7 @@ fn main() {
         writable: true,
     };
 
-    match File::open(filename) {
+    match File::open(&filename) {
         Ok(mut f) => {
             // Load ROM image
             match f.read(&mut rom.data) {
@@ -60,32 +76,47 @@ fn main() {
                 Err(e) => println!("errro: {}", e),
             }
 
-            let mut device_map: device::SystemMap = device::SystemMap { map: Vec::new() };
+            let mut device_map: device::SystemMap = device::SystemMap::new();
             device_map.register_device(ram);
             device_map.register_device(rom);
 
             let mut system: cpu::M0System = cpu::M0System::new(device_map);
+            system.multiplier = multiplier;
+            system.fault_action = fault_action;
+            for injection in injections {
+                system.inject(injection);
+            }
 
             println!("reset vector {}", system.system_map.read32(0).unwrap());
-            let mut cycle_count: u32 = 1;
-
             system.reset();
             system.dump();
 
             println!("*EXECUTE BINARY");
             loop {
-                println!("");
-                print!("clk:{}\t", cycle_count);
-                let elapsed_cycle: u32 = system.execute();
-                if elapsed_cycle > 0 {
-                    cycle_count += elapsed_cycle;
+                println!();
+                print!("clk:{}\t", system.cycle_count);
+                if system.execute() > 0 {
+                    continue;
+                }
+                if system.state == cpu::RunState::Lockup {
+                    println!();
+                    print!("{}", system.lockup_report());
+                    println!("*LOCKUP (EXIT)");
+                    exit(2);
+                } else if !system.fault_chain.is_empty() {
+                    println!();
+                    for (n, fault) in system.fault_chain.iter().enumerate() {
+                        println!("  fault {}: {}", n, fault);
+                    }
+                    println!("*STOP ON FAULT (EXIT)");
+                    exit(3);
                 } else {
-                    println!("");
+                    println!();
                     println!("*FATAL ERROR (EXIT)");
                     break;
                 }
             }
         }
-        Err(e) => println!("error {} {}", args[1], e),
+        Err(e) => println!("error {} {}", filename, e),
     }
 }
@@ -0,0 +1,162 @@
+use crate::device::DeviceAccess;
+use crate::device::DeviceMapping;
+
+pub const NVIC_ADRS: u32 = 0xe000e100;
+pub const NVIC_SIZE: usize = 0x320;
+
+pub const NVIC_ISER: u32 = 0xe000e100;
+pub const NVIC_ICER: u32 = 0xe000e180;
+pub const NVIC_ISPR: u32 = 0xe000e200;
+pub const NVIC_ICPR: u32 = 0xe000e280;
+pub const NVIC_IPR0: u32 = 0xe000e400;
+pub const NVIC_IPR7: u32 = 0xe000e41c;
+
+pub const IRQ_LINES: usize = 32;
+
+// Cortex-M0 implements the upper 2 bits of each 8 bit priority field
+const PRIORITY_MASK: u8 = 0xc0;
+
+#[derive(Debug)]
+pub struct Nvic {
+    pub mapping: DeviceMapping,
+    pub enable: u32,
+    pub pending: u32,
+    pub priority: [u8; IRQ_LINES],
+}
+
+impl Default for Nvic {
+    fn default() -> Self {
+        Self {
+            mapping: DeviceMapping {
+                adrs: NVIC_ADRS,
+                size: NVIC_SIZE,
+            },
+            enable: 0,
+            pending: 0,
+            priority: [0; IRQ_LINES],
+        }
+    }
+}
+
+impl Nvic {
+    pub fn raise_irq(&mut self, irq: usize) {
+        println!("\t IRQ{} raised", irq);
+        self.pending |= 0b1 << irq;
+    }
+
+    pub fn clear_irq(&mut self, irq: usize) {
+        println!("\t IRQ{} cleared", irq);
+        self.pending &= !(0b1 << irq);
+    }
+
+    pub fn is_pending(&self, irq: usize) -> bool {
+        (self.pending & self.enable) & (0b1 << irq) != 0
+    }
+}
+
+impl DeviceAccess for Nvic {
+    fn get_range(&self) -> DeviceMapping {
+        DeviceMapping { ..self.mapping }
+    }
+
+    fn set_range(&mut self, range: DeviceMapping) {
+        self.mapping = range;
+    }
+
+    // NVIC registers are only word accessible
+    fn read8(&self, _adrs: u32) -> Option<u8> {
+        None
+    }
+
+    fn read16(&self, _adrs: u32) -> Option<u16> {
+        None
+    }
+
+    fn read32(&self, adrs: u32) -> Option<u32> {
+        match adrs {
+            NVIC_ISER | NVIC_ICER => Some(self.enable),
+            NVIC_ISPR | NVIC_ICPR => Some(self.pending),
+            NVIC_IPR0..=NVIC_IPR7 if adrs & 0b11 == 0 => {
+                let index: usize = (adrs - NVIC_IPR0) as usize;
+                let mut val: u32 = 0;
+                for i in 0..4 {
+                    val |= (self.priority[index + i] as u32) << (8 * i);
+                }
+                Some(val)
+            }
+            _ if self.is_mapped(adrs) => Some(0),
+            _ => None,
+        }
+    }
+
+    fn write8(&mut self, _adrs: u32, _val: u8) -> Option<()> {
+        None
+    }
+
+    fn write16(&mut self, _adrs: u32, _val: u16) -> Option<()> {
+        None
+    }
+
+    fn write32(&mut self, adrs: u32, val: u32) -> Option<()> {
+        match adrs {
+            NVIC_ISER => self.enable |= val,
+            NVIC_ICER => self.enable &= !val,
+            NVIC_ISPR => self.pending |= val,
+            NVIC_ICPR => self.pending &= !val,
+            NVIC_IPR0..=NVIC_IPR7 if adrs & 0b11 == 0 => {
+                let index: usize = (adrs - NVIC_IPR0) as usize;
+                for i in 0..4 {
+                    self.priority[index + i] = ((val >> (8 * i)) as u8) & PRIORITY_MASK;
+                }
+            }
+            _ if self.is_mapped(adrs) => (),
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nvic_registers() {
+        let mut nvic: Nvic = Nvic::default();
+
+        nvic.write32(NVIC_ISER, 0b1011).unwrap();
+        nvic.write32(NVIC_ICER, 0b0010).unwrap();
+        assert_eq!(nvic.read32(NVIC_ISER), Some(0b1001));
+        assert_eq!(nvic.read32(NVIC_ICER), Some(0b1001));
+
+        nvic.write32(NVIC_ISPR, 0b1100).unwrap();
+        nvic.write32(NVIC_ICPR, 0b0100).unwrap();
+        assert_eq!(nvic.read32(NVIC_ISPR), Some(0b1000));
+        assert_eq!(nvic.read32(NVIC_ICPR), Some(0b1000));
+
+        // only the upper 2 bits of the priority fields are implemented
+        nvic.write32(NVIC_IPR0 + 4, 0xffc08040).unwrap();
+        assert_eq!(nvic.read32(NVIC_IPR0 + 4), Some(0xc0c08040));
+        assert_eq!(nvic.priority[4], 0x40);
+        assert_eq!(nvic.priority[7], 0xc0);
+
+        assert_eq!(nvic.read8(NVIC_ISER), None);
+        assert_eq!(nvic.write16(NVIC_ISER, 1), None);
+        assert_eq!(nvic.read32(NVIC_IPR7 + 4), None);
+    }
+
+    #[test]
+    fn test_nvic_irq_lines() {
+        let mut nvic: Nvic = Nvic::default();
+
+        // a raised line is pending but only taken when enabled
+        nvic.raise_irq(5);
+        assert_eq!(nvic.read32(NVIC_ISPR), Some(0b100000));
+        assert!(!nvic.is_pending(5));
+        nvic.write32(NVIC_ISER, 0b100000).unwrap();
+        assert!(nvic.is_pending(5));
+        nvic.clear_irq(5);
+        assert!(!nvic.is_pending(5));
+        assert_eq!(nvic.read32(NVIC_ISPR), Some(0));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::cpu::M0System;
+use crate::exception::*;
+
+pub const SCB_ADRS: u32 = 0xe000ed00;
+pub const SCB_SIZE: u32 = 0x40;
+
+pub const SCB_CPUID: u32 = 0xe000ed00;
+pub const SCB_ICSR: u32 = 0xe000ed04;
+pub const SCB_VTOR: u32 = 0xe000ed08;
+pub const SCB_AIRCR: u32 = 0xe000ed0c;
+pub const SCB_SCR: u32 = 0xe000ed10;
+pub const SCB_CCR: u32 = 0xe000ed14;
+pub const SCB_SHPR2: u32 = 0xe000ed1c;
+pub const SCB_SHPR3: u32 = 0xe000ed20;
+pub const SCB_SHCSR: u32 = 0xe000ed24;
+pub const SCB_DFSR: u32 = 0xe000ed30;
+
+pub const ICSR_PENDSVCLR: u32 = 0b1 << 27;
+pub const ICSR_PENDSTCLR: u32 = 0b1 << 25;
+pub const ICSR_ISRPENDING: u32 = 0b1 << 22;
+
+pub const AIRCR_VECTKEY: u32 = 0x05fa;
+pub const AIRCR_VECTKEYSTAT: u32 = 0xfa05;
+pub const AIRCR_SYSRESETREQ: u32 = 0b1 << 2;
+
+const SCR_MASK: u32 = 0b10110;
+const SHPR2_MASK: u32 = 0xc0000000;
+const SHPR3_MASK: u32 = 0xc0c00000;
+
+pub fn is_scb_mapped(adrs: u32) -> bool {
+    (SCB_ADRS..SCB_ADRS + SCB_SIZE).contains(&adrs)
+}
+
+// SCB registers are only word accessible
+pub fn scb_read32(system: &M0System, adrs: u32) -> Option<u32> {
+    let cpu = &system.cpu;
+    match adrs {
+        SCB_CPUID => Some(cpu.cpuid),
+        SCB_ICSR => {
+            let pending: u32 = cpu.icsr & (ICSR_NMIPENDSET | ICSR_PENDSVSET | ICSR_PENDSTSET);
+            let vectpending: u32 = pending_exception(system).unwrap_or(0) as u32;
+            let isrpending: u32 =
+                if system.system_map.nvic.pending & system.system_map.nvic.enable != 0 {
+                    ICSR_ISRPENDING
+                } else {
+                    0
+                };
+            Some(pending | isrpending | (vectpending << 12) | (cpu.ipsr & 0x3f))
+        }
+        SCB_VTOR => Some(cpu.vtor),
+        SCB_AIRCR => Some((AIRCR_VECTKEYSTAT << 16) | (cpu.aircr & 0xffff)),
+        SCB_SCR => Some(cpu.scr),
+        SCB_CCR => Some(cpu.ccr),
+        SCB_SHPR2 => Some(cpu.shpr2),
+        SCB_SHPR3 => Some(cpu.shpr3),
+        SCB_SHCSR => Some(cpu.shcsr),
+        SCB_DFSR => Some(cpu.dfsr),
+        _ if is_scb_mapped(adrs) && adrs & 0b11 == 0 => Some(0),
+        _ => None,
+    }
+}
+
+pub fn scb_write32(system: &mut M0System, adrs: u32, val: u32) -> Option<()> {
+    let cpu = &mut system.cpu;
+    match adrs {
+        SCB_ICSR => {
+            // write one to set or clear the pending state
+            cpu.icsr |= val & (ICSR_NMIPENDSET | ICSR_PENDSVSET | ICSR_PENDSTSET);
+            if val & ICSR_PENDSVCLR != 0 {
+                cpu.icsr &= !ICSR_PENDSVSET;
+            }
+            if val & ICSR_PENDSTCLR != 0 {
+                cpu.icsr &= !ICSR_PENDSTSET;
+            }
+        }
+        SCB_VTOR => cpu.vtor = val & 0xffffff80,
+        SCB_AIRCR => {
+            // writes without the key are ignored, SYSRESETREQ is handled after the instruction
+            if val >> 16 == AIRCR_VECTKEY {
+                cpu.aircr = val & AIRCR_SYSRESETREQ;
+            }
+        }
+        SCB_SCR => cpu.scr = val & SCR_MASK,
+        SCB_SHPR2 => cpu.shpr2 = val & SHPR2_MASK,
+        SCB_SHPR3 => cpu.shpr3 = val & SHPR3_MASK,
+        SCB_SHCSR => cpu.shcsr = val & SHCSR_SVCALLPENDED,
+        // write one to clear
+        SCB_DFSR => cpu.dfsr &= !val,
+        _ if is_scb_mapped(adrs) && adrs & 0b11 == 0 => (),
+        _ => return None,
+    }
+    Some(())
+}
@@ -0,0 +1,191 @@
+use std::cell::Cell;
+
+use crate::device::DeviceAccess;
+use crate::device::DeviceMapping;
+
+pub const SYSTICK_ADRS: u32 = 0xe000e010;
+pub const SYSTICK_SIZE: usize = 0x10;
+
+pub const SYST_CSR: u32 = 0xe000e010;
+pub const SYST_RVR: u32 = 0xe000e014;
+pub const SYST_CVR: u32 = 0xe000e018;
+pub const SYST_CALIB: u32 = 0xe000e01c;
+
+pub const CSR_ENABLE: u32 = 0b1;
+pub const CSR_TICKINT: u32 = 0b1 << 1;
+pub const CSR_CLKSOURCE: u32 = 0b1 << 2;
+pub const CSR_COUNTFLAG: u32 = 0b1 << 16;
+
+// no reference clock and no calibration value
+const CALIB_VALUE: u32 = 0xc0000000;
+
+#[derive(Debug)]
+pub struct SysTick {
+    pub mapping: DeviceMapping,
+    pub csr: u32,
+    pub rvr: u32,
+    pub cvr: u32,
+    // COUNTFLAG is cleared by reading CSR
+    pub countflag: Cell<bool>,
+}
+
+impl Default for SysTick {
+    fn default() -> Self {
+        Self {
+            mapping: DeviceMapping {
+                adrs: SYSTICK_ADRS,
+                size: SYSTICK_SIZE,
+            },
+            csr: CSR_CLKSOURCE,
+            rvr: 0,
+            cvr: 0,
+            countflag: Cell::new(false),
+        }
+    }
+}
+
+impl SysTick {
+    // advances the counter by the elapsed cycles, returns true when the SysTick exception is raised
+    pub fn tick(&mut self, cycles: u32) -> bool {
+        let mut raise: bool = false;
+        if self.csr & CSR_ENABLE == 0 {
+            return false;
+        }
+        for _ in 0..cycles {
+            if self.cvr == 0 {
+                self.cvr = self.rvr;
+            } else {
+                self.cvr -= 1;
+                if self.cvr == 0 {
+                    self.countflag.set(true);
+                    raise |= self.csr & CSR_TICKINT != 0;
+                }
+            }
+        }
+        raise
+    }
+}
+
+impl DeviceAccess for SysTick {
+    fn get_range(&self) -> DeviceMapping {
+        DeviceMapping { ..self.mapping }
+    }
+
+    fn set_range(&mut self, range: DeviceMapping) {
+        self.mapping = range;
+    }
+
+    // SysTick registers are only word accessible
+    fn read8(&self, _adrs: u32) -> Option<u8> {
+        None
+    }
+
+    fn read16(&self, _adrs: u32) -> Option<u16> {
+        None
+    }
+
+    fn read32(&self, adrs: u32) -> Option<u32> {
+        match adrs {
+            SYST_CSR => {
+                let countflag: u32 = if self.countflag.replace(false) {
+                    CSR_COUNTFLAG
+                } else {
+                    0
+                };
+                Some(self.csr | countflag)
+            }
+            SYST_RVR => Some(self.rvr),
+            SYST_CVR => Some(self.cvr),
+            SYST_CALIB => Some(CALIB_VALUE),
+            _ => None,
+        }
+    }
+
+    fn write8(&mut self, _adrs: u32, _val: u8) -> Option<()> {
+        None
+    }
+
+    fn write16(&mut self, _adrs: u32, _val: u16) -> Option<()> {
+        None
+    }
+
+    fn write32(&mut self, adrs: u32, val: u32) -> Option<()> {
+        match adrs {
+            // without a reference clock CLKSOURCE is fixed to the processor clock
+            SYST_CSR => self.csr = (val & (CSR_ENABLE | CSR_TICKINT)) | CSR_CLKSOURCE,
+            SYST_RVR => self.rvr = val & 0x00ffffff,
+            SYST_CVR => {
+                // any write clears the counter and COUNTFLAG
+                self.cvr = 0;
+                self.countflag.set(false);
+            }
+            SYST_CALIB => (),
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systick_registers() {
+        let mut systick: SysTick = SysTick::default();
+
+        systick.write32(SYST_RVR, 0xff000100).unwrap();
+        assert_eq!(systick.read32(SYST_RVR), Some(0x00000100));
+        assert_eq!(systick.read32(SYST_CALIB), Some(CALIB_VALUE));
+        systick.write32(SYST_CALIB, 0).unwrap();
+        assert_eq!(systick.read32(SYST_CALIB), Some(CALIB_VALUE));
+        assert_eq!(systick.read32(SYST_CSR), Some(CSR_CLKSOURCE));
+        systick.write32(SYST_CSR, 0).unwrap();
+        assert_eq!(systick.read32(SYST_CSR), Some(CSR_CLKSOURCE));
+        assert_eq!(systick.read8(SYST_CSR), None);
+
+        // disabled counter does not move
+        assert!(!systick.tick(10));
+        assert_eq!(systick.read32(SYST_CVR), Some(0));
+    }
+
+    #[test]
+    fn test_systick_count() {
+        let mut systick: SysTick = SysTick::default();
+        systick.write32(SYST_RVR, 4).unwrap();
+        systick.write32(SYST_CVR, 1234).unwrap();
+        systick
+            .write32(SYST_CSR, CSR_ENABLE | CSR_CLKSOURCE)
+            .unwrap();
+
+        // reload from 0 takes one cycle, then counts down RVR cycles
+        assert!(!systick.tick(1));
+        assert_eq!(systick.read32(SYST_CVR), Some(4));
+        assert!(!systick.tick(3));
+        assert_eq!(systick.read32(SYST_CVR), Some(1));
+        assert_eq!(systick.read32(SYST_CSR), Some(CSR_ENABLE | CSR_CLKSOURCE));
+
+        // COUNTFLAG is set on 1 -> 0 and cleared by a read
+        assert!(!systick.tick(1));
+        assert_eq!(
+            systick.read32(SYST_CSR),
+            Some(CSR_ENABLE | CSR_CLKSOURCE | CSR_COUNTFLAG)
+        );
+        assert_eq!(systick.read32(SYST_CSR), Some(CSR_ENABLE | CSR_CLKSOURCE));
+
+        // TICKINT raises the exception every RVR + 1 cycles, CLKSOURCE can not be cleared
+        systick.write32(SYST_CSR, CSR_ENABLE | CSR_TICKINT).unwrap();
+        assert!(!systick.tick(4));
+        assert!(systick.tick(1));
+        assert!(!systick.tick(4));
+        assert!(systick.tick(3));
+        assert_eq!(systick.read32(SYST_CVR), Some(3));
+
+        // writing CVR clears COUNTFLAG
+        systick.write32(SYST_CVR, 0).unwrap();
+        assert_eq!(
+            systick.read32(SYST_CSR),
+            Some(CSR_ENABLE | CSR_TICKINT | CSR_CLKSOURCE)
+        );
+    }
+}